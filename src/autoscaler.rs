@@ -0,0 +1,207 @@
+// SPDX-FileCopyrightText: © 2024 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+/// Policy to decide the number of serverless workers executing the
+/// classical tasks.
+pub trait Autoscaler: Send {
+    /// Return the number of workers desired, which is then bounded by the
+    /// minimum and maximum number of workers.
+    /// Parameters:
+    /// - `now`: the current time, in ns
+    /// - `num_tasks`: the number of classical tasks in execution
+    /// - `num_workers`: the number of workers currently available
+    fn desired_workers(&mut self, now: u64, num_tasks: usize, num_workers: usize) -> usize;
+}
+
+/// Keep the number of classical tasks per worker close to a target.
+pub struct TargetConcurrency {
+    target: f64,
+}
+
+impl Autoscaler for TargetConcurrency {
+    fn desired_workers(&mut self, _now: u64, num_tasks: usize, _num_workers: usize) -> usize {
+        (num_tasks as f64 / self.target).ceil() as usize
+    }
+}
+
+/// Add a worker when the number of classical tasks exceeding the workers
+/// reaches a threshold and remove one when the number of idle workers
+/// reaches another threshold.
+pub struct QueueThreshold {
+    up: usize,
+    down: usize,
+}
+
+impl Autoscaler for QueueThreshold {
+    fn desired_workers(&mut self, _now: u64, num_tasks: usize, num_workers: usize) -> usize {
+        if num_tasks.saturating_sub(num_workers) >= self.up {
+            num_workers + 1
+        } else if num_workers.saturating_sub(num_tasks) >= self.down {
+            num_workers - 1
+        } else {
+            num_workers
+        }
+    }
+}
+
+/// Autoscaler similar to that of Knative: the number of workers follows the
+/// average concurrency over a stable window, unless the average concurrency
+/// over a shorter panic window requires at least twice as many workers as
+/// currently available, in which case the number of workers follows the
+/// panic window and it is not reduced until a stable window has elapsed.
+pub struct Knative {
+    target: f64,
+    stable_window: u64,
+    panic_window: u64,
+    samples: std::collections::VecDeque<(u64, usize)>,
+    panic_until: Option<u64>,
+}
+
+impl Knative {
+    /// Create a Knative-like autoscaler.
+    /// Parameters:
+    /// - `target`: the target number of classical tasks per worker
+    /// - `stable_window`: the duration of the stable window, in ns
+    /// - `panic_window`: the duration of the panic window, in ns
+    pub fn new(target: f64, stable_window: u64, panic_window: u64) -> Self {
+        Self {
+            target,
+            stable_window,
+            panic_window,
+            samples: std::collections::VecDeque::new(),
+            panic_until: None,
+        }
+    }
+
+    /// Return the number of workers needed for the average concurrency
+    /// in the given window.
+    fn window_workers(&self, now: u64, window: u64) -> usize {
+        let samples = self
+            .samples
+            .iter()
+            .filter(|(time, _)| *time + window > now)
+            .map(|(_, num_tasks)| *num_tasks)
+            .collect::<Vec<usize>>();
+        let avg = samples.iter().sum::<usize>() as f64 / samples.len() as f64;
+        (avg / self.target).ceil() as usize
+    }
+}
+
+impl Autoscaler for Knative {
+    fn desired_workers(&mut self, now: u64, num_tasks: usize, num_workers: usize) -> usize {
+        self.samples.push_back((now, num_tasks));
+        while let Some((time, _)) = self.samples.front() {
+            if *time + self.stable_window > now {
+                break;
+            }
+            self.samples.pop_front();
+        }
+
+        let stable_workers = self.window_workers(now, self.stable_window);
+        let panic_workers = self.window_workers(now, self.panic_window);
+        if panic_workers >= 2 * num_workers {
+            self.panic_until = Some(now + self.stable_window);
+        }
+        match self.panic_until {
+            Some(panic_until) if now < panic_until => std::cmp::max(num_workers, panic_workers),
+            _ => {
+                self.panic_until = None;
+                stable_workers
+            }
+        }
+    }
+}
+
+/// Factory of autoscalers.
+/// Parameters:
+/// - `policy`: one of `concurrency;TARGET`, `queue;UP;DOWN`,
+///   `knative;TARGET;STABLE;PANIC`, with windows in s
+pub fn make_autoscaler(policy: &str) -> anyhow::Result<Box<dyn Autoscaler>> {
+    let tokens = policy.split(';').collect::<Vec<&str>>();
+    let values = tokens[1..]
+        .iter()
+        .map(|x| x.parse::<f64>())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|_| anyhow::anyhow!("invalid autoscaler parameters: {}", policy))?;
+    anyhow::ensure!(
+        values.iter().all(|x| *x > 0.0),
+        "non-positive autoscaler parameters: {}",
+        policy
+    );
+    match (tokens[0], values.len()) {
+        ("concurrency", 1) => Ok(Box::new(TargetConcurrency { target: values[0] })),
+        ("queue", 2) => Ok(Box::new(QueueThreshold {
+            up: values[0].round() as usize,
+            down: values[1].round() as usize,
+        })),
+        ("knative", 3) => {
+            anyhow::ensure!(
+                values[2] <= values[1],
+                "panic window longer than stable window: {}",
+                policy
+            );
+            Ok(Box::new(Knative::new(
+                values[0],
+                (values[1] * 1e9).round() as u64,
+                (values[2] * 1e9).round() as u64,
+            )))
+        }
+        _ => anyhow::bail!("cannot parse '{}' as autoscaler policy", policy),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_autoscaler() -> anyhow::Result<()> {
+        let mut concurrency = make_autoscaler("concurrency;2")?;
+        assert_eq!(0, concurrency.desired_workers(0, 0, 4));
+        assert_eq!(3, concurrency.desired_workers(0, 5, 4));
+
+        let mut queue = make_autoscaler("queue;2;3")?;
+        assert_eq!(5, queue.desired_workers(0, 6, 4));
+        assert_eq!(4, queue.desired_workers(0, 5, 4));
+        assert_eq!(4, queue.desired_workers(0, 2, 4));
+        assert_eq!(3, queue.desired_workers(0, 1, 4));
+
+        for invalid in [
+            "",
+            "concurrency",
+            "concurrency;0",
+            "queue;1",
+            "knative;1;6;60",
+            "unknown;1",
+        ] {
+            assert!(make_autoscaler(invalid).is_err(), "{}", invalid);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_knative_autoscaler() -> anyhow::Result<()> {
+        let second = 1_000_000_000;
+        let mut knative = Knative::new(1.0, 60 * second, 6 * second);
+
+        // stable mode
+        for i in 0..60 {
+            assert_eq!(2, knative.desired_workers(i * second, 2, 2));
+        }
+
+        // burst: the panic window reacts quickly
+        assert_eq!(3, knative.desired_workers(60 * second, 8, 2));
+        assert_eq!(4, knative.desired_workers(61 * second, 8, 2));
+
+        // the burst ends: no scale down while panicking
+        for i in 62..121 {
+            assert!(knative.desired_workers(i * second, 0, 4) >= 4);
+        }
+
+        // the panic mode ends with no tasks during the stable window
+        assert_eq!(0, knative.desired_workers(200 * second, 0, 4));
+
+        Ok(())
+    }
+}
@@ -26,24 +26,193 @@ struct Args {
     /// The number of quantum computers
     #[arg(long, default_value_t = 2)]
     num_quantum_computers: usize,
+    /// The specifications of heterogeneous quantum computers, separated by
+    /// semicolons, each as NAME:MAX_QUBITS:SPEED, optionally prefixed by
+    /// N* to add N identical ones (e.g., "2*falcon:27:1;heron:133:2");
+    /// if not empty, it overrides --num-quantum-computers
+    #[arg(long, default_value_t = String::from(""))]
+    quantum_computers: String,
+    /// The policy to select the quantum computer executing a task, one of:
+    /// first-fit, best-fit, fastest, least-loaded, random
+    #[arg(long, default_value_t = String::from("first-fit"))]
+    qpu_selection_policy: String,
+    /// The organization of the queues of pending quantum tasks, one of:
+    /// central, per-qpu (tasks routed to the shortest queue when ready),
+    /// per-qpu-stealing (like per-qpu, but idle QPUs steal tasks)
+    #[arg(long, default_value_t = String::from("central"))]
+    quantum_queue_mode: String,
+    /// The sessions that reserve a QPU for a job during its iterative phase,
+    /// either a single value applied to all priorities or a list of
+    /// PRIORITY:TIMEOUT separated by semicolons, where TIMEOUT is the idle
+    /// time, in s, after which the QPU is released, or 'inf' (e.g., "4:inf;2:30")
+    #[arg(long, default_value_t = String::from(""))]
+    sessions: String,
+    /// The policy to execute quantum tasks in batches, as
+    /// MAX_SIZE:MAX_WAIT:OVERHEAD:SCALE, where a batch lasts OVERHEAD plus
+    /// SCALE times the sum of the durations of its circuits, and MAX_WAIT is
+    /// the maximum time a task waits for the batch to be filled, all in s
+    /// (e.g., "8:30:2:0.9"); batch execution is disabled if empty
+    #[arg(long, default_value_t = String::from(""))]
+    batch: String,
+    /// The policy to execute concurrently on the same quantum computer
+    /// circuits whose total number of qubits fits, as SLOWDOWN:PENALTY,
+    /// where the execution of every circuit is slowed down by SLOWDOWN,
+    /// relatively, for every other circuit executing at the same time, hence
+    /// the circuits in execution slow down when a new one starts and speed up
+    /// when one ends, and the fidelity of a circuit is reduced by PENALTY,
+    /// relatively, for every other circuit executing when it starts (e.g.,
+    /// "0.1:0.05"); multi-programming is disabled if empty and requires
+    /// --quantum-computers, which limits the qubits of every quantum computer
+    #[arg(long, default_value_t = String::from(""))]
+    multi_programming: String,
+    /// The downtimes of the quantum computers, either as PERIOD:DURATION,
+    /// in s, for periodic calibrations of all the quantum computers, staggered
+    /// evenly over the period, or as the path of a CSV file with lines
+    /// QPU_ID,START,DURATION, in s;
+    /// the quantum computers are always online if empty
+    #[arg(long, default_value_t = String::from(""))]
+    downtimes: String,
+    /// What happens to the quantum tasks in execution when a downtime
+    /// begins, one of: finish, abort (the job is dropped), requeue
+    #[arg(long, default_value_t = String::from("finish"))]
+    downtime_policy: String,
+    /// The random failures of the quantum computers, as MTBF:MTTR, i.e.,
+    /// the mean time between failures and the mean time to repair, in s,
+    /// both exponentially distributed; a failure interrupts the quantum
+    /// tasks in execution; the quantum computers never fail if empty
+    #[arg(long, default_value_t = String::from(""))]
+    failures: String,
+    /// The maximum number of times the quantum tasks of a job are executed
+    /// again after failures of the quantum computers before the job fails
+    #[arg(long, default_value_t = 3)]
+    retry_budget: u32,
+    /// The distribution of the cold-start latency of the serverless function
+    /// instances executing the classical tasks, in s, one of: VALUE,
+    /// exp:MEAN, uniform:MIN:MAX; there are no cold starts if empty
+    #[arg(long, default_value_t = String::from(""))]
+    cold_start: String,
+    /// The time a serverless function instance is kept warm after executing
+    /// a classical task of a job, in s
+    #[arg(long, default_value_t = 60.0)]
+    keep_alive: f64,
+    /// The policy to scale the number of serverless workers, which is
+    /// initially equal to --num-serverless-workers, one of:
+    /// concurrency;TARGET (TARGET classical tasks per worker),
+    /// queue;UP;DOWN (add a worker with UP tasks waiting, remove a worker
+    /// with DOWN workers idle), knative;TARGET;STABLE;PANIC (windows in s);
+    /// the number of workers is fixed if empty
+    #[arg(long, default_value_t = String::from(""))]
+    autoscaler: String,
+    /// The minimum number of serverless workers, with autoscaling
+    #[arg(long, default_value_t = 1)]
+    min_workers: usize,
+    /// The maximum number of serverless workers, with autoscaling
+    #[arg(long, default_value_t = 100)]
+    max_workers: usize,
+    /// The time needed to add serverless workers, in s
+    #[arg(long, default_value_t = 0.0)]
+    scale_up_delay: f64,
+    /// The interval between consecutive autoscaling decisions, in s
+    #[arg(long, default_value_t = 2.0)]
+    autoscaler_interval: f64,
+    /// The policy to share the serverless workers among the classical tasks,
+    /// one of: processor-sharing (the capacity is split equally among all
+    /// the tasks), dps (the capacity is split in proportion to the job
+    /// priorities), strict-priority (the capacity is split equally among the
+    /// tasks with highest job priority first), fifo, priority (each task
+    /// holds a worker exclusively, the others wait in order of arrival or
+    /// job priority)
+    #[arg(long, default_value_t = String::from("processor-sharing"))]
+    classical_scheduler: String,
+    /// The distribution of the latency to submit a quantum task to a remote
+    /// quantum computer, including the queueing overhead at the provider,
+    /// in s, one of: VALUE, exp:MEAN, uniform:MIN:MAX; no latency if empty
+    #[arg(long, default_value_t = String::from(""))]
+    submission_latency: String,
+    /// The distribution of the latency to retrieve the result of a quantum
+    /// task from a remote quantum computer, in s, one of: VALUE, exp:MEAN,
+    /// uniform:MIN:MAX; no latency if empty
+    #[arg(long, default_value_t = String::from(""))]
+    retrieval_latency: String,
+    /// The sizes of the payloads submitted to and retrieved from a remote
+    /// quantum computer, in bytes, as REQUEST:RESPONSE, which are transferred
+    /// on a link with given --bandwidth; no payloads if empty
+    #[arg(long, default_value_t = String::from(""))]
+    payload: String,
+    /// The bandwidth of the link to the remote quantum computers, in Mb/s
+    #[arg(long, default_value_t = 100.0)]
+    bandwidth: f64,
     /// The maximum queue length for classical tasks
     #[arg(long, default_value_t = 50)]
     max_classical_tasks: usize,
     /// The maximum queue length for quantum tasks
     #[arg(long, default_value_t = 50)]
     max_quantum_tasks: usize,
-    /// The policy to schedule quantum tasks.
+    /// The policy to schedule quantum tasks, one of: fifo, lifo, random,
+    /// weighted, priority, aging;RATE (with RATE the increase of priority
+    /// per second of waiting), srpt, las, edf (earliest deadline first,
+    /// which needs --deadlines, otherwise it behaves like fifo)
     #[arg(long, default_value_t = String::from("fifo"))]
     quantum_schedule_policy: String,
-    /// The job type
+    /// The job types, as groups separated by '|', each with the algorithm,
+    /// either VQE, QAOA with optional depth as QAOA:P (default is 1), or
+    /// WORKFLOW:NAME, followed by the numbers of qubits, separated by ';'
+    /// (e.g., "VQE;4;8|QAOA:3;6;10"); the traces of QAOA, with values per
+    /// layer of the circuit, including the number of iterations, scaled
+    /// linearly with the depth, are read from input/qaoa/, the description
+    /// of a workflow from input/workflows/NAME.txt
     #[arg(long, default_value_t = String::from("VQE;4;6;8;10"))]
     job_type: String,
+    /// The number of quantum tasks, executed in parallel, into which every
+    /// quantum iteration of VQE and QAOA jobs is split evenly, one of: N,
+    /// pow:COEFF:EXPONENT (i.e., COEFF times the number of qubits raised to
+    /// EXPONENT), trace (drawn for every job from quantum_tasks.csv in the
+    /// directory of the traces)
+    #[arg(long, default_value_t = String::from("1"))]
+    quantum_tasks: String,
+    /// The model of the number of iterations of VQE and QAOA jobs, one of:
+    /// trace (drawn for every job from num_iterations.csv), hazard:MAX
+    /// (after every iteration, the job stops with the probability given by
+    /// the hazard function fitted from num_iterations.csv, with at most MAX
+    /// iterations)
+    #[arg(long, default_value_t = String::from("trace"))]
+    convergence: String,
+    /// Let the policies see only the expected number of iterations left of
+    /// VQE and QAOA jobs, given those completed so far, instead of the true
+    /// one, e.g., with the srpt policy or deadlines relative to the expected
+    /// service time; always the case with the hazard convergence
+    #[arg(long, default_value_t = false)]
+    hide_job_sizes: bool,
+    /// The variability across the iterations of VQE and QAOA jobs of their
+    /// classical and quantum durations, one of: fixed (drawn once for every
+    /// job), resample (drawn for every iteration), ar1:CORRELATION:STDDEV
+    /// (drawn once for every job and multiplied in every iteration by
+    /// log-normal factors with unit mean, whose logarithms follow an AR(1)
+    /// process with given correlation and standard deviation)
+    #[arg(long, default_value_t = String::from("fixed"))]
+    iteration_variability: String,
     /// The job priorities
     #[arg(long, default_value_t = String::from("1;2;4"))]
     priorities: String,
+    /// The relative deadlines of jobs, either a single value applied to all
+    /// priorities or a list of PRIORITY:DEADLINE separated by semicolons,
+    /// where DEADLINE is a number of seconds or, if prefixed by 'x', a
+    /// multiple of the expected service time of the job (e.g., "1:x4;2:120")
+    #[arg(long, default_value_t = String::from(""))]
+    deadlines: String,
     /// Save iteration durations
     #[arg(long, default_value_t = false)]
     save_iteration_durations: bool,
+    /// Save a record of every job completed in jobs.csv
+    #[arg(long, default_value_t = false)]
+    save_jobs: bool,
+    /// Save a trace of all the events processed and the tasks started and
+    /// ended, one of: jsonl (in trace.jsonl, one JSON object per line),
+    /// chrome (in trace.json, with the tasks executed on the quantum
+    /// computers and serverless workers in Chrome trace-event format, which
+    /// cannot be used with --append); no trace is saved if empty
+    #[arg(long, default_value_t = String::from(""))]
+    trace: String,
     /// Print trace stats and quit
     #[arg(long, default_value_t = false)]
     trace_stats: bool,
@@ -85,6 +254,19 @@ fn open_output_file(
             Ok(metadata) => metadata.len() == 0,
             Err(_) => true,
         };
+    if !add_header {
+        // the layout of the output may change between versions, e.g., the
+        // job type has been added to the labels of the series
+        let mut existing_header = String::new();
+        std::io::BufReader::new(std::fs::File::open(&output_single_filename)?)
+            .read_line(&mut existing_header)?;
+        anyhow::ensure!(
+            existing_header.trim_end() == header,
+            "cannot append to '{}', whose header differs from: {}",
+            output_single_filename,
+            header
+        );
+    }
     let mut f = std::fs::OpenOptions::new()
         .write(true)
         .append(append)
@@ -137,33 +319,84 @@ async fn main() -> anyhow::Result<()> {
 
     // print trace statistics and quit, if applicable
     if args.trace_stats {
-        let trace_stats = serverless_quantum_sim::job::JobFactory::new(0, &target_dur_qc_avg)
-            .unwrap()
-            .trace_stats();
-        let mut alt: std::collections::BTreeMap<u16, std::collections::HashMap<String, f64>> =
-            std::collections::BTreeMap::new();
-        for (elem, records) in trace_stats {
-            println!("{}", elem);
-            for record in records {
-                println!(
-                    "num_qubits {:>3} -> {} / {} / {}",
-                    record.0, record.1, record.2, record.3
-                );
-                alt.entry(record.0)
-                    .or_default()
-                    .insert(elem.clone(), record.2);
+        let job_types = serverless_quantum_sim::job::parse_job_types(&args.job_type)?;
+        let trace_stats = serverless_quantum_sim::job::JobFactory::new(
+            0,
+            &job_types,
+            &target_dur_qc_avg,
+            args.quantum_tasks
+                .parse::<serverless_quantum_sim::job::QuantumTasks>()?,
+            args.convergence
+                .parse::<serverless_quantum_sim::job::Convergence>()?,
+            args.hide_job_sizes,
+            args.iteration_variability
+                .parse::<serverless_quantum_sim::job::IterationVariability>()?,
+        )?
+        .trace_stats();
+        for (algorithm, algorithm_trace_stats) in trace_stats {
+            let mut alt: std::collections::BTreeMap<u16, std::collections::HashMap<String, f64>> =
+                std::collections::BTreeMap::new();
+            for (elem, records) in algorithm_trace_stats {
+                println!("{} {}", algorithm, elem);
+                for record in records {
+                    println!(
+                        "num_qubits {:>3} -> {} / {} / {}",
+                        record.0, record.1, record.2, record.3
+                    );
+                    alt.entry(record.0)
+                        .or_default()
+                        .insert(elem.clone(), record.2);
+                }
+            }
+            println!("{} average job times", algorithm);
+            for (num_qubits, values) in alt {
+                let avg_job_time = values["pre"]
+                    + values["num_iterations"] * (values["iter"] + values["dur_qc"])
+                    + values["post"];
+                println!("num_qubits {:>3} -> {}", num_qubits, avg_job_time);
             }
-        }
-        println!("average job times");
-        for (num_qubits, values) in alt {
-            let avg_job_time = values["pre"]
-                + values["num_iterations"] * (values["iter"] + values["dur_qc"])
-                + values["post"];
-            println!("num_qubits {:>3} -> {}", num_qubits, avg_job_time);
         }
         return Ok(());
     }
 
+    // create the path to write output files, if needed
+    let test_filename = format!("{}test.csv", args.output_path);
+    let test_path = std::path::Path::new(&test_filename);
+    let mut ancestors = test_path.ancestors();
+    ancestors.next();
+    let base_dir = ancestors.next().unwrap();
+    std::fs::create_dir_all(base_dir)?;
+
+    // open the trace file, which the simulations write while running
+    let trace_writer = if args.trace.is_empty() {
+        None
+    } else {
+        let format = args
+            .trace
+            .parse::<serverless_quantum_sim::trace::TraceFormat>()?;
+        let file = match format {
+            serverless_quantum_sim::trace::TraceFormat::Chrome => {
+                anyhow::ensure!(
+                    !args.append,
+                    "cannot append to a trace in Chrome trace-event format"
+                );
+                std::fs::File::create(format!("{}trace.json", args.output_path))?
+            }
+            serverless_quantum_sim::trace::TraceFormat::JsonLines => std::fs::OpenOptions::new()
+                .write(true)
+                .append(args.append)
+                .create(true)
+                .truncate(!args.append)
+                .open(format!("{}trace.jsonl", args.output_path))?,
+        };
+        Some(std::sync::Arc::new(std::sync::Mutex::new(
+            serverless_quantum_sim::trace::TraceWriter::new(
+                format,
+                Box::new(std::io::BufWriter::new(file)),
+            )?,
+        )))
+    };
+
     // create the configurations of all the experiments
     let configurations = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
     for seed in args.seed_init..args.seed_end {
@@ -178,12 +411,41 @@ async fn main() -> anyhow::Result<()> {
                 worker_capacity: args.worker_capacity,
                 num_serverless_workers: args.num_serverless_workers,
                 num_quantum_computers: args.num_quantum_computers,
+                quantum_computers: args.quantum_computers.clone(),
+                qpu_selection_policy: args.qpu_selection_policy.clone(),
+                quantum_queue_mode: args.quantum_queue_mode.clone(),
+                sessions: args.sessions.clone(),
+                batch: args.batch.clone(),
+                multi_programming: args.multi_programming.clone(),
+                downtimes: args.downtimes.clone(),
+                downtime_policy: args.downtime_policy.clone(),
+                failures: args.failures.clone(),
+                retry_budget: args.retry_budget,
+                cold_start: args.cold_start.clone(),
+                keep_alive: args.keep_alive,
+                autoscaler: args.autoscaler.clone(),
+                min_workers: args.min_workers,
+                max_workers: args.max_workers,
+                scale_up_delay: args.scale_up_delay,
+                autoscaler_interval: args.autoscaler_interval,
+                classical_scheduler: args.classical_scheduler.clone(),
+                submission_latency: args.submission_latency.clone(),
+                retrieval_latency: args.retrieval_latency.clone(),
+                payload: args.payload.clone(),
+                bandwidth: args.bandwidth,
                 max_classical_tasks: args.max_classical_tasks,
                 max_quantum_tasks: args.max_quantum_tasks,
                 quantum_schedule_policy: args.quantum_schedule_policy.clone(),
                 job_type: args.job_type.clone(),
+                quantum_tasks: args.quantum_tasks.clone(),
+                convergence: args.convergence.clone(),
+                hide_job_sizes: args.hide_job_sizes,
+                iteration_variability: args.iteration_variability.clone(),
                 priorities: args.priorities.clone(),
+                deadlines: args.deadlines.clone(),
                 save_iteration_durations: args.save_iteration_durations,
+                save_jobs: args.save_jobs,
+                trace: trace_writer.clone(),
                 target_dur_qc_avg: target_dur_qc_avg.clone(),
             });
     }
@@ -223,17 +485,14 @@ async fn main() -> anyhow::Result<()> {
         outputs.push(output);
     }
 
+    // complete the trace, if any
+    if let Some(trace_writer) = trace_writer {
+        trace_writer.lock().unwrap().finish()?;
+    }
+
     // save output to files
     anyhow::ensure!(!outputs.is_empty(), "no simulation output has been found");
 
-    // create the path to write output files, if needed
-    let test_filename = format!("{}test.csv", args.output_path);
-    let test_path = std::path::Path::new(&test_filename);
-    let mut ancestors = test_path.ancestors();
-    ancestors.next();
-    let base_dir = ancestors.next().unwrap();
-    std::fs::create_dir_all(base_dir)?;
-
     // write simulation output
     let mut single_file = open_output_file(
         &args.output_path,
@@ -248,6 +507,23 @@ async fn main() -> anyhow::Result<()> {
         .as_str(),
     )?;
 
+    let mut jobs_file = if args.save_jobs {
+        Some(open_output_file(
+            &args.output_path,
+            "jobs.csv",
+            args.append,
+            format!(
+                "{}{},{}",
+                args.additional_header,
+                serverless_quantum_sim::simulation::Config::header(),
+                serverless_quantum_sim::job::Job::header()
+            )
+            .as_str(),
+        )?)
+    } else {
+        None
+    };
+
     for output in outputs {
         writeln!(
             &mut single_file,
@@ -280,6 +556,16 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
         }
+
+        if let Some(jobs_file) = &mut jobs_file {
+            for job in &output.jobs {
+                writeln!(
+                    jobs_file,
+                    "{}{},{}",
+                    args.additional_fields, output.config_csv, job
+                )?;
+            }
+        }
     }
 
     Ok(())
@@ -0,0 +1,226 @@
+// SPDX-FileCopyrightText: © 2024 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+/// Policy to share the serverless workers among the classical tasks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClassicalScheduler {
+    /// The capacity of the workers is split equally among all the tasks,
+    /// without exceeding the capacity of a single worker per task.
+    ProcessorSharing,
+    /// The capacity of the workers is split among all the tasks in
+    /// proportion to the priorities of their jobs, without exceeding the
+    /// capacity of a single worker per task.
+    DiscriminatoryProcessorSharing,
+    /// The capacity of the workers is split equally among the tasks of the
+    /// jobs with the highest priority, without exceeding the capacity of a
+    /// single worker per task, and what is left goes to lower priorities.
+    StrictPriorityProcessorSharing,
+    /// Each task holds a worker exclusively until completion, while the
+    /// others wait in order of arrival.
+    Fifo,
+    /// Each task holds a worker exclusively until completion, while the
+    /// others wait in order of decreasing priority of their jobs, then
+    /// in order of arrival.
+    Priority,
+}
+
+impl ClassicalScheduler {
+    /// Return true if every task holds a worker exclusively.
+    pub fn is_dedicated(&self) -> bool {
+        matches!(self, Self::Fifo | Self::Priority)
+    }
+
+    /// Return the capacity assigned to each task by the policies that share
+    /// the workers among all the tasks.
+    /// Parameters:
+    /// - `priorities`: the priorities of the jobs of the tasks
+    /// - `total_capacity`: the capacity of all the workers
+    /// - `worker_capacity`: the capacity of a single worker
+    pub fn shares(
+        &self,
+        priorities: &[u16],
+        total_capacity: f64,
+        worker_capacity: f64,
+    ) -> Vec<f64> {
+        assert!(!self.is_dedicated());
+        let mut shares = vec![0.0; priorities.len()];
+        if let Self::StrictPriorityProcessorSharing = self {
+            let mut remaining = total_capacity;
+            let classes = priorities
+                .iter()
+                .collect::<std::collections::BTreeSet<&u16>>();
+            for priority in classes.into_iter().rev() {
+                let members = (0..priorities.len())
+                    .filter(|index| priorities[*index] == *priority)
+                    .collect::<Vec<usize>>();
+                let share = f64::min(worker_capacity, remaining / members.len() as f64);
+                for index in &members {
+                    shares[*index] = share;
+                }
+                remaining = f64::max(0.0, remaining - share * members.len() as f64);
+            }
+            return shares;
+        }
+
+        // the tasks whose share would exceed the capacity of a worker are
+        // assigned a worker, the rest is split among the others
+        let weights = priorities
+            .iter()
+            .map(|priority| match self {
+                Self::DiscriminatoryProcessorSharing => *priority as f64,
+                _ => 1.0,
+            })
+            .collect::<Vec<f64>>();
+        let mut remaining = total_capacity;
+        let mut uncapped = (0..priorities.len()).collect::<Vec<usize>>();
+        while !uncapped.is_empty() {
+            let sum_weights = uncapped.iter().map(|index| weights[*index]).sum::<f64>();
+            let (capped, others): (Vec<usize>, Vec<usize>) = uncapped
+                .iter()
+                .partition(|index| remaining * weights[**index] / sum_weights >= worker_capacity);
+            if capped.is_empty() {
+                for index in others {
+                    shares[index] = remaining * weights[index] / sum_weights;
+                }
+                break;
+            }
+            for index in capped {
+                shares[index] = worker_capacity;
+                remaining -= worker_capacity;
+            }
+            uncapped = others;
+        }
+        shares
+    }
+
+    /// Select the next waiting task to be assigned a worker, if any.
+    /// Parameters:
+    /// - `tasks`: the classical tasks, where those waiting have no resource
+    /// - `active_jobs`: the active jobs, indexed by their identifiers
+    ///
+    /// Return the index in `tasks` of the task selected.
+    pub fn next_waiting(
+        &self,
+        tasks: &[crate::task::Task],
+        active_jobs: &std::collections::HashMap<u64, crate::job::Job>,
+    ) -> Option<usize> {
+        let waiting = tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| task.resource.is_none());
+        match self {
+            Self::ProcessorSharing
+            | Self::DiscriminatoryProcessorSharing
+            | Self::StrictPriorityProcessorSharing => None,
+            Self::Fifo => waiting
+                .min_by_key(|(_, task)| task.start_time)
+                .map(|(index, _)| index),
+            Self::Priority => waiting
+                .min_by_key(|(_, task)| {
+                    (
+                        std::cmp::Reverse(active_jobs.get(&task.job_id).unwrap().priority),
+                        task.start_time,
+                    )
+                })
+                .map(|(index, _)| index),
+        }
+    }
+}
+
+impl std::str::FromStr for ClassicalScheduler {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "processor-sharing" => Ok(Self::ProcessorSharing),
+            "dps" => Ok(Self::DiscriminatoryProcessorSharing),
+            "strict-priority" => Ok(Self::StrictPriorityProcessorSharing),
+            "fifo" => Ok(Self::Fifo),
+            "priority" => Ok(Self::Priority),
+            _ => anyhow::bail!("cannot parse '{}' as classical scheduler", s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_task(job_id: u64, start_time: u64, resource: Option<usize>) -> crate::task::Task {
+        crate::task::Task {
+            job_id,
+            task_type: crate::task::TaskType::Classical(1000),
+            start_time,
+            last_update: start_time,
+            resource,
+            branch: 0,
+        }
+    }
+
+    #[test]
+    fn test_classical_scheduler() -> anyhow::Result<()> {
+        use std::str::FromStr;
+
+        let mut active_jobs = std::collections::HashMap::new();
+        for (job_id, priority) in [(0, 1), (1, 1), (2, 2), (3, 4)] {
+            active_jobs.insert(job_id, crate::job::Job::for_testing(job_id, 4, priority));
+        }
+        let tasks = vec![
+            make_task(0, 30, None),
+            make_task(1, 10, None),
+            make_task(2, 20, None),
+            make_task(3, 0, Some(0)),
+        ];
+
+        let expected = [
+            ("processor-sharing", None),
+            ("fifo", Some(1)),
+            ("priority", Some(2)),
+        ];
+        for (policy, index) in expected {
+            let scheduler = ClassicalScheduler::from_str(policy)?;
+            assert_eq!(policy != "processor-sharing", scheduler.is_dedicated());
+            assert_eq!(
+                index,
+                scheduler.next_waiting(&tasks, &active_jobs),
+                "{}",
+                policy
+            );
+        }
+        assert_eq!(
+            None,
+            ClassicalScheduler::Fifo.next_waiting(&tasks[3..], &active_jobs)
+        );
+        assert!(ClassicalScheduler::from_str("ps").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_classical_scheduler_shares() -> anyhow::Result<()> {
+        use std::str::FromStr;
+
+        let priorities = [1, 1, 2, 4];
+        let expected = [
+            ("processor-sharing", [1.0, 1.0, 1.0, 1.0], 4.0),
+            ("processor-sharing", [0.75, 0.75, 0.75, 0.75], 3.0),
+            ("dps", [0.375, 0.375, 0.75, 1.0], 2.5),
+            ("dps", [0.25, 0.25, 0.5, 1.0], 2.0),
+            ("strict-priority", [0.0, 0.0, 1.0, 1.0], 2.0),
+            ("strict-priority", [0.25, 0.25, 1.0, 1.0], 2.5),
+            ("strict-priority", [0.0, 0.0, 0.5, 1.0], 1.5),
+        ];
+        for (policy, shares, total_capacity) in expected {
+            let scheduler = ClassicalScheduler::from_str(policy)?;
+            assert!(!scheduler.is_dedicated());
+            assert_eq!(
+                shares.to_vec(),
+                scheduler.shares(&priorities, total_capacity, 1.0),
+                "{} {}",
+                policy,
+                total_capacity
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -3,46 +3,364 @@
 
 use average::{concatenate, Estimate, Max, Mean, Min};
 use rand::seq::SliceRandom;
+use rand::Rng;
 use rand::SeedableRng;
+use rand_distr::Distribution;
 use std::io::BufRead;
 
 const MILLISECOND: u64 = 1_000_000;
 const SECOND: u64 = 1_000 * MILLISECOND;
 
-#[derive(Debug)]
+/// Header of the labels of the jobs.
+pub const LABEL_HEADER: &str = "job_type,num_qubits,priority";
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum JobType {
     /// Variational Quantum Eigensolver with variable number of qubits
     /// read from traces.
     Vqe(u16),
+    /// Quantum Approximate Optimization Algorithm with variable number of
+    /// qubits read from traces and given depth p of the circuit.
+    Qaoa(u16, u16),
+    /// Workflow with a given name, read from its description, and number
+    /// of qubits of its quantum circuits.
+    Workflow(String, u16),
 }
 
-#[derive(Debug)]
-pub enum JobStatus {
+impl JobType {
+    /// Return the number of qubits.
+    pub fn num_qubits(&self) -> u16 {
+        match self {
+            Self::Vqe(num_qubits) | Self::Qaoa(num_qubits, _) | Self::Workflow(_, num_qubits) => {
+                *num_qubits
+            }
+        }
+    }
+
+    /// Return the algorithm, with its parameters other than the number of
+    /// qubits, e.g., `VQE`, `QAOA:3`, or `WORKFLOW:NAME`.
+    pub fn algorithm(&self) -> String {
+        match self {
+            Self::Vqe(_) => "VQE".to_string(),
+            Self::Qaoa(_, depth) => format!("QAOA:{}", depth),
+            Self::Workflow(name, _) => format!("WORKFLOW:{}", name),
+        }
+    }
+
+    /// Return the label of the jobs of this type with a given priority.
+    pub fn label(&self, priority: u16) -> String {
+        format!("{},{},{}", self.algorithm(), self.num_qubits(), priority)
+    }
+}
+
+/// Parse the job types, specified as groups separated by `|`, each with
+/// the algorithm followed by the numbers of qubits, separated by `;`, where
+/// the algorithm is either `VQE`, `QAOA`, with optional depth `QAOA:P`
+/// (default is 1), or `WORKFLOW:NAME`, e.g., `VQE;4;8|QAOA:3;6;10`.
+pub fn parse_job_types(s: &str) -> anyhow::Result<Vec<JobType>> {
+    let mut ret = vec![];
+    for group in s.split('|') {
+        let tokens = group.split(';').collect::<Vec<&str>>();
+        let (algorithm, parameter) = match tokens[0].split_once(':') {
+            Some((algorithm, parameter)) => (algorithm.to_ascii_lowercase(), Some(parameter)),
+            None => (tokens[0].to_ascii_lowercase(), None),
+        };
+        let make_job_type: Box<dyn Fn(u16) -> JobType> = match (algorithm.as_str(), parameter) {
+            ("vqe", None) => Box::new(JobType::Vqe),
+            ("qaoa", None) => Box::new(|num_qubits| JobType::Qaoa(num_qubits, 1)),
+            ("qaoa", Some(depth)) => {
+                let depth = depth
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|x| *x > 0)
+                    .ok_or(anyhow::anyhow!("invalid QAOA depth: {}", tokens[0]))?;
+                Box::new(move |num_qubits| JobType::Qaoa(num_qubits, depth))
+            }
+            ("workflow", Some(name)) if !name.is_empty() => {
+                Box::new(|num_qubits| JobType::Workflow(name.to_string(), num_qubits))
+            }
+            _ => anyhow::bail!("invalid job type: {}", tokens[0]),
+        };
+        anyhow::ensure!(
+            tokens.len() > 1,
+            "too few qubits specified for job type: {}",
+            group
+        );
+        for token in &tokens[1..] {
+            let num_qubits = token.parse::<u16>().map_err(|_| {
+                anyhow::anyhow!("cannot parse number of qubits in job type: {}", group)
+            })?;
+            ret.push(make_job_type(num_qubits));
+        }
+    }
+    Ok(ret)
+}
+
+/// Number of quantum tasks, executed in parallel, into which every quantum
+/// iteration of VQE and QAOA jobs is split, e.g., to evaluate independently
+/// the commuting groups of the Hamiltonian or the parameter shifts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuantumTasks {
+    /// Always the same number.
+    Fixed(u64),
+    /// A coefficient times the number of qubits raised to an exponent,
+    /// rounded to the nearest integer, but at least 1.
+    Power(f64, f64),
+    /// Drawn for every job from the trace of its algorithm.
+    Trace,
+}
+
+impl std::str::FromStr for QuantumTasks {
+    type Err = anyhow::Error;
+    /// Parse the number of quantum tasks per iteration, specified as one of:
+    /// `N`, `pow:COEFF:EXPONENT`, `trace`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = s.split(':').collect::<Vec<&str>>();
+        match tokens[..] {
+            ["trace"] => Ok(Self::Trace),
+            ["pow", coeff, exponent] => match (coeff.parse::<f64>(), exponent.parse::<f64>()) {
+                (Ok(coeff), Ok(exponent)) if coeff > 0.0 && exponent.is_finite() => {
+                    Ok(Self::Power(coeff, exponent))
+                }
+                _ => anyhow::bail!("invalid quantum tasks formula: {}", s),
+            },
+            [value] => match value.parse::<u64>() {
+                Ok(value) if value > 0 => Ok(Self::Fixed(value)),
+                _ => anyhow::bail!("invalid number of quantum tasks: {}", s),
+            },
+            _ => anyhow::bail!("invalid number of quantum tasks: {}", s),
+        }
+    }
+}
+
+/// Number of iterations of VQE and QAOA jobs, until their optimizer
+/// converges.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Convergence {
+    /// Drawn for every job from the trace of its algorithm.
+    Trace,
+    /// After every iteration, the job stops with the probability given by
+    /// the hazard function fitted from the trace of its algorithm, i.e., the
+    /// probability that the number of iterations is the current one given
+    /// that it is not smaller, with at most a given number of iterations.
+    /// Since the number of iterations is only known when the job stops, the
+    /// policies see the expected number of iterations left.
+    Hazard(u64),
+}
+
+impl std::str::FromStr for Convergence {
+    type Err = anyhow::Error;
+    /// Parse the convergence model, specified as one of: `trace`,
+    /// `hazard:MAX`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = s.split(':').collect::<Vec<&str>>();
+        match tokens[..] {
+            ["trace"] => Ok(Self::Trace),
+            ["hazard", max_iterations] => match max_iterations.parse::<u64>() {
+                Ok(max_iterations) if max_iterations > 0 => Ok(Self::Hazard(max_iterations)),
+                _ => anyhow::bail!("invalid maximum number of iterations: {}", s),
+            },
+            _ => anyhow::bail!("invalid convergence model: {}", s),
+        }
+    }
+}
+
+/// Variability across the iterations of VQE and QAOA jobs of the number of
+/// operations of their classical part and the duration of their quantum part.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IterationVariability {
+    /// The same values for all the iterations, drawn once for every job from
+    /// the traces of its algorithm.
+    Fixed,
+    /// Values drawn independently for every iteration from the traces of
+    /// the algorithm of the job.
+    Resample,
+    /// Values drawn once for every job from the traces of its algorithm and
+    /// multiplied in every iteration by log-normal factors with unit mean,
+    /// whose logarithms follow a first-order autoregressive process, with
+    /// a given correlation between consecutive iterations and standard
+    /// deviation. The factors of the classical and quantum parts are
+    /// independent.
+    Ar1(f64, f64),
+}
+
+impl std::str::FromStr for IterationVariability {
+    type Err = anyhow::Error;
+    /// Parse the variability of the iterations, specified as one of:
+    /// `fixed`, `resample`, `ar1:CORRELATION:STDDEV`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = s.split(':').collect::<Vec<&str>>();
+        match tokens[..] {
+            ["fixed"] => Ok(Self::Fixed),
+            ["resample"] => Ok(Self::Resample),
+            ["ar1", correlation, stddev] => {
+                match (correlation.parse::<f64>(), stddev.parse::<f64>()) {
+                    (Ok(correlation), Ok(stddev))
+                        if (0.0..1.0).contains(&correlation)
+                            && (0.0..f64::INFINITY).contains(&stddev) =>
+                    {
+                        Ok(Self::Ar1(correlation, stddev))
+                    }
+                    _ => anyhow::bail!("invalid autoregressive iteration variability: {}", s),
+                }
+            }
+            _ => anyhow::bail!("invalid iteration variability: {}", s),
+        }
+    }
+}
+
+/// Deadline of a job, relative to its arrival time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RelativeDeadline {
+    /// Fixed deadline, in ns.
+    Fixed(u64),
+    /// Multiple of the expected service time of the job, i.e., the time
+    /// it would take to complete the job if it never waited for resources.
+    ServiceTimeMultiple(f64),
+}
+
+impl std::str::FromStr for RelativeDeadline {
+    type Err = anyhow::Error;
+    /// Parse a relative deadline, which can be specified either as a fixed
+    /// number of seconds (e.g., `120`) or as a multiple of the expected
+    /// service time if prefixed by `x` (e.g., `x2.5`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(multiple) = s.strip_prefix('x') {
+            match multiple.parse::<f64>() {
+                Ok(multiple) if multiple > 0.0 => Ok(Self::ServiceTimeMultiple(multiple)),
+                _ => anyhow::bail!("invalid service time multiple in deadline: {}", s),
+            }
+        } else {
+            match s.parse::<f64>() {
+                Ok(seconds) if seconds > 0.0 => {
+                    Ok(Self::Fixed((seconds * SECOND as f64).round() as u64))
+                }
+                _ => anyhow::bail!("invalid deadline: {}", s),
+            }
+        }
+    }
+}
+
+/// Phase of a job, in which its time is accounted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
     Preparation,
-    ClassicalIteration(u64),
-    QuantumIteration(u64),
+    ClassicalIteration,
+    QuantumIteration,
     Postprocessing,
-    Completed,
+}
+
+/// Stage of a job, which starts when all the tasks of the previous
+/// stage are complete.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stage {
+    /// Classical task of a given phase, with its number of operations.
+    Classical(Phase, u64),
+    /// Quantum tasks executed in parallel, with their durations on a QC
+    /// with nominal speed, in ns.
+    Quantum(Vec<u64>),
+}
+
+/// Return the stages of a job made of a preparation phase, a number of
+/// iterations, each with a classical task and a given number of quantum
+/// tasks that split evenly the duration of the quantum iteration, and
+/// a post-processing phase.
+/// Parameters:
+/// - `num_operations_pre`: the number of operations of the preparation phase
+/// - `iterations`: the number of operations of the classical part and the
+///   duration of the quantum part, in ns, of every iteration
+/// - `num_operations_post`: the number of operations of the post-processing
+///   phase
+/// - `num_quantum_tasks`: the number of quantum tasks per iteration
+fn pipeline(
+    num_operations_pre: u64,
+    iterations: &[(u64, u64)],
+    num_operations_post: u64,
+    num_quantum_tasks: u64,
+) -> Vec<Stage> {
+    let mut stages = Vec::with_capacity(2 * iterations.len() + 2);
+    stages.push(Stage::Classical(Phase::Preparation, num_operations_pre));
+    for (num_operations_iter, dur_qc_iteration) in iterations {
+        stages.push(Stage::Classical(
+            Phase::ClassicalIteration,
+            *num_operations_iter,
+        ));
+        stages.push(Stage::Quantum(
+            (0..num_quantum_tasks)
+                .map(|i| {
+                    dur_qc_iteration / num_quantum_tasks
+                        + u64::from(i < dur_qc_iteration % num_quantum_tasks)
+                })
+                .collect(),
+        ));
+    }
+    stages.push(Stage::Classical(Phase::Postprocessing, num_operations_post));
+    stages
+}
+
+/// Totals of the stages of a job.
+struct StageSummary {
+    /// Number of operations of the classical tasks, per phase.
+    num_operations: [u64; 4],
+    /// Number of quantum iterations, i.e., stages with quantum tasks.
+    num_iterations: u64,
+    /// Number of quantum tasks.
+    num_quantum_tasks: u64,
+    /// Time to execute all the quantum tasks on a QC, in ns.
+    total_quantum_time: u64,
+}
+
+impl StageSummary {
+    /// Compute the totals of the given stages.
+    fn new(stages: &[Stage]) -> Self {
+        let mut summary = Self {
+            num_operations: [0; 4],
+            num_iterations: 0,
+            num_quantum_tasks: 0,
+            total_quantum_time: 0,
+        };
+        for stage in stages {
+            match stage {
+                Stage::Classical(phase, value) => summary.num_operations[*phase as usize] += value,
+                Stage::Quantum(durations) => {
+                    summary.num_iterations += 1;
+                    summary.num_quantum_tasks += durations.len() as u64;
+                    summary.total_quantum_time += durations.iter().sum::<u64>();
+                }
+            }
+        }
+        summary
+    }
+
+    /// Return a total divided by the number of quantum iterations, if any.
+    fn per_iteration(&self, value: u64) -> u64 {
+        value.checked_div(self.num_iterations).unwrap_or(value)
+    }
 }
 
 #[derive(Debug)]
 pub struct Job {
-    #[allow(dead_code)]
     /// Job type.
     job_type: JobType,
-    /// Job status.
-    job_status: JobStatus,
+    /// Stages of the job.
+    stages: Vec<Stage>,
+    /// Index of the next stage to be started.
+    next_stage: usize,
+    /// Number of tasks of the current stage not completed yet.
+    pending_tasks: usize,
     /// Numeric application identifier.
     pub job_id: u64,
     /// Number of operations for the preparation phase.
     num_operations_pre: u64,
-    /// Number of operations for each iteration.
+    /// Number of operations for the classical iterations, on average per
+    /// quantum iteration.
     num_operations_iter: u64,
     /// Number of operations for the post-processing phase.
     num_operations_post: u64,
-    /// Time to execute a single iteration on a QC, in ns.
+    /// Time to execute the tasks of a quantum iteration on a QC, on average,
+    /// in ns.
     dur_qc_iteration: u64,
-    /// Number of iterations.
+    /// Number of quantum iterations, i.e., stages with quantum tasks.
     num_iterations: u64,
     /// Arrival time, in ns.
     pub time_arrival: u64,
@@ -52,49 +370,367 @@ pub struct Job {
     pub priority: u16,
     /// Label.
     pub label: String,
+    /// Absolute deadline, in ns, if any.
+    pub deadline: Option<u64>,
+    /// Number of times a quantum task has been executed again after
+    /// a failure of the quantum computer.
+    pub num_retries: u32,
+    /// Time, in ns, until which the serverless function instance of the job
+    /// is kept warm, if any.
+    pub warm_until: Option<u64>,
+    /// Time spent so far in hand-offs between the classical and quantum
+    /// stages, in ns: for a quantum stage, that of its last task completed.
+    pub network_time: u64,
+    /// Time spent so far by the classical tasks, from their creation until
+    /// their completion, in ns.
+    pub classical_time: u64,
+    /// Time spent so far by the quantum stages, from the end of the hand-off
+    /// of their last task completed until its completion, in ns.
+    pub quantum_time: u64,
+    /// Time spent so far executing the last task completed of every quantum
+    /// stage, in ns.
+    pub quantum_service_time: u64,
+    /// Number of quantum tasks.
+    num_quantum_tasks: u64,
+    /// Number of quantum tasks completed so far.
+    completed_quantum_tasks: u64,
+    /// Time to execute the quantum tasks not completed yet on a QC with
+    /// nominal speed, in ns.
+    remaining_quantum_time: u64,
+    /// Position of the next quantum task to be accounted as completed:
+    /// index of the stage and of the task within the stage.
+    quantum_cursor: (usize, usize),
+    /// Number of quantum iterations whose tasks have all been completed.
+    completed_quantum_iterations: u64,
+    /// Expected number of quantum iterations left, indexed by the number of
+    /// those completed, if the policies cannot see the true size of the job.
+    expected_iterations_left: Option<std::sync::Arc<Vec<f64>>>,
+    /// Probability of stopping after every number of quantum iterations,
+    /// with the RNG of the decisions, if the job does not execute all the
+    /// iterations of its stages.
+    stop_hazards: Option<(std::sync::Arc<Vec<f64>>, rand::rngs::StdRng)>,
+    /// Time spent so far executing quantum tasks, in ns.
+    attained_quantum_time: u64,
+    /// Time spent so far in each phase, in ns: preparation, classical
+    /// iterations, quantum iterations, post-processing.
+    phase_times: [u64; 4],
+    /// The phase of the last task created and the time of its creation, in ns.
+    last_phase: Option<(usize, u64)>,
 }
 
 impl Job {
-    pub fn next_task(&mut self, cur_time: u64) -> Option<crate::task::Task> {
-        let task_type = match &self.job_status {
-            JobStatus::Preparation => {
-                self.job_status = JobStatus::ClassicalIteration(1);
-                crate::task::TaskType::Classical(self.num_operations_pre)
+    /// Create a job that executes the given stages.
+    /// Parameters:
+    /// - `job_type`: the job type
+    /// - `job_id`: the job identifier
+    /// - `priority`: the job priority
+    /// - `time_arrival`: the time of arrival of this job, in ns
+    /// - `stages`: the stages of the job
+    fn new(
+        job_type: JobType,
+        job_id: u64,
+        priority: u16,
+        time_arrival: u64,
+        stages: Vec<Stage>,
+    ) -> Self {
+        let summary = StageSummary::new(&stages);
+        Self {
+            num_qubits: job_type.num_qubits(),
+            label: job_type.label(priority),
+            job_type,
+            stages,
+            next_stage: 0,
+            pending_tasks: 0,
+            job_id,
+            num_operations_pre: summary.num_operations[Phase::Preparation as usize],
+            num_operations_iter: summary
+                .per_iteration(summary.num_operations[Phase::ClassicalIteration as usize]),
+            num_operations_post: summary.num_operations[Phase::Postprocessing as usize],
+            dur_qc_iteration: summary.per_iteration(summary.total_quantum_time),
+            num_iterations: summary.num_iterations,
+            time_arrival,
+            priority,
+            deadline: None,
+            num_retries: 0,
+            warm_until: None,
+            network_time: 0,
+            classical_time: 0,
+            quantum_time: 0,
+            quantum_service_time: 0,
+            num_quantum_tasks: summary.num_quantum_tasks,
+            completed_quantum_tasks: 0,
+            remaining_quantum_time: summary.total_quantum_time,
+            quantum_cursor: (0, 0),
+            completed_quantum_iterations: 0,
+            expected_iterations_left: None,
+            stop_hazards: None,
+            attained_quantum_time: 0,
+            phase_times: [0; 4],
+            last_phase: None,
+        }
+    }
+
+    /// Create a job with fixed parameters, for unit tests only.
+    #[cfg(test)]
+    pub(crate) fn for_testing(job_id: u64, num_qubits: u16, priority: u16) -> Self {
+        Self::new(
+            JobType::Vqe(num_qubits),
+            job_id,
+            priority,
+            0,
+            pipeline(SECOND, &[(MILLISECOND, SECOND); 10], SECOND, 1),
+        )
+    }
+
+    /// Notify the job that one of its quantum tasks has been completed.
+    /// The tasks of a stage are accounted in order, even though they
+    /// may complete in any order.
+    /// Return true if this is the last task of its stage to complete.
+    /// Parameters:
+    /// - `execution_time`: the time spent executing the task on a QC, in ns
+    pub fn quantum_task_completed(&mut self, execution_time: u64) -> bool {
+        let mut stage_completed = false;
+        while let Some(stage) = self.stages.get(self.quantum_cursor.0) {
+            match stage {
+                Stage::Quantum(durations) if self.quantum_cursor.1 < durations.len() => {
+                    self.remaining_quantum_time -= durations[self.quantum_cursor.1];
+                    self.quantum_cursor.1 += 1;
+                    if self.quantum_cursor.1 == durations.len() {
+                        self.completed_quantum_iterations += 1;
+                        stage_completed = true;
+                    }
+                    break;
+                }
+                _ => self.quantum_cursor = (self.quantum_cursor.0 + 1, 0),
             }
-            JobStatus::ClassicalIteration(num_iteration) => {
-                self.job_status = JobStatus::QuantumIteration(*num_iteration);
-                crate::task::TaskType::Classical(self.num_operations_iter)
+        }
+        self.completed_quantum_tasks += 1;
+        self.attained_quantum_time += execution_time;
+        stage_completed
+    }
+
+    /// Return the time, in ns, when the current stage started.
+    pub fn stage_start_time(&self) -> u64 {
+        self.last_phase
+            .map(|(_, since)| since)
+            .unwrap_or(self.time_arrival)
+    }
+
+    /// Return the expected time, in ns, to execute the quantum tasks
+    /// that have not been completed yet, as seen by the policies: if the
+    /// size of the job is hidden, it is estimated from the expected number
+    /// of quantum iterations left, given those completed so far.
+    pub fn remaining_quantum_time(&self) -> u64 {
+        match &self.expected_iterations_left {
+            Some(expected_iterations_left) => {
+                let iterations_left = expected_iterations_left
+                    .get(self.completed_quantum_iterations as usize)
+                    .unwrap_or(&0.0);
+                (iterations_left * self.dur_qc_iteration as f64).round() as u64
             }
-            JobStatus::QuantumIteration(num_iteration) => {
-                if *num_iteration == self.num_iterations {
-                    self.job_status = JobStatus::Postprocessing;
-                } else {
-                    self.job_status = JobStatus::ClassicalIteration(*num_iteration + 1);
-                }
-                crate::task::TaskType::Quantum(self.dur_qc_iteration)
+            None => self.remaining_quantum_time,
+        }
+    }
+
+    /// Return the duration, in ns, of a quantum task of the current stage
+    /// of this job when executed on a quantum computer with nominal speed.
+    /// Parameters:
+    /// - `branch`: the index of the task within the stage
+    pub fn quantum_iteration_duration(&self, branch: usize) -> u64 {
+        match &self.stages[self.next_stage - 1] {
+            Stage::Quantum(durations) => durations[branch],
+            Stage::Classical(..) => panic!("not a quantum stage"),
+        }
+    }
+
+    /// Return true if the job has quantum tasks not completed yet.
+    pub fn has_quantum_iterations_left(&self) -> bool {
+        self.completed_quantum_tasks < self.num_quantum_tasks
+    }
+
+    /// Return the time, in ns, spent so far executing quantum tasks.
+    pub fn attained_quantum_time(&self) -> u64 {
+        self.attained_quantum_time
+    }
+
+    /// Return the time, in ns, that it would take to complete the job if it
+    /// never waited for resources, as seen by the policies: if the size of
+    /// the job is hidden, the duration of its iterations is scaled to their
+    /// expected number.
+    /// Parameters:
+    /// - `worker_capacity`: the capacity of a serverless worker, in operations/s
+    pub fn expected_service_time(&self, worker_capacity: u64) -> u64 {
+        let quantum_service_time = self
+            .stages
+            .iter()
+            .map(|stage| match stage {
+                Stage::Quantum(durations) => *durations.iter().max().unwrap(),
+                Stage::Classical(..) => 0,
+            })
+            .sum::<u64>();
+        let service_time = self.classical_service_time(worker_capacity) + quantum_service_time;
+        match &self.expected_iterations_left {
+            Some(expected_iterations_left) if self.num_iterations > 0 => {
+                let iterations_time = quantum_service_time as f64
+                    + (self.num_iterations * self.num_operations_iter) as f64 * SECOND as f64
+                        / worker_capacity as f64;
+                let scale = expected_iterations_left[0] / self.num_iterations as f64;
+                (service_time as f64 + iterations_time * (scale - 1.0)).round() as u64
             }
-            JobStatus::Postprocessing => {
-                self.job_status = JobStatus::Completed;
-                crate::task::TaskType::Classical(self.num_operations_post)
+            _ => service_time,
+        }
+    }
+
+    /// Return the time, in ns, that it would take to execute all the
+    /// classical tasks of the job, each on a dedicated serverless worker.
+    /// Parameters:
+    /// - `worker_capacity`: the capacity of a serverless worker, in operations/s
+    pub fn classical_service_time(&self, worker_capacity: u64) -> u64 {
+        let num_operations = self
+            .stages
+            .iter()
+            .map(|stage| match stage {
+                Stage::Classical(_, num_operations) => *num_operations,
+                Stage::Quantum(_) => 0,
+            })
+            .sum::<u64>();
+        (num_operations as f64 * SECOND as f64 / worker_capacity as f64).round() as u64
+    }
+
+    /// Return the header of the record of a completed job.
+    pub fn header() -> String {
+        "job_id,label,job_type,num_qubits,priority,arrival,completion,num_iterations,num_operations_pre,num_operations_iter,num_operations_post,dur_qc_iteration,num_retries,preparation_time,classical_iteration_time,quantum_iteration_time,postprocessing_time".to_string()
+    }
+
+    /// Return the record of a completed job, with times in s.
+    /// Parameters:
+    /// - `completion`: the completion time of the job, in ns
+    pub fn to_csv(&self, completion: u64) -> String {
+        format!(
+            "{},\"{}\",{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.job_id,
+            self.label,
+            self.job_type.algorithm(),
+            self.num_qubits,
+            self.priority,
+            crate::simulation::to_seconds(self.time_arrival),
+            crate::simulation::to_seconds(completion),
+            self.num_iterations,
+            self.num_operations_pre,
+            self.num_operations_iter,
+            self.num_operations_post,
+            crate::simulation::to_seconds(self.dur_qc_iteration),
+            self.num_retries,
+            crate::simulation::to_seconds(self.phase_times[0]),
+            crate::simulation::to_seconds(self.phase_times[1]),
+            crate::simulation::to_seconds(self.phase_times[2]),
+            crate::simulation::to_seconds(self.phase_times[3])
+        )
+    }
+
+    /// Set the absolute deadline of the job.
+    /// Parameters:
+    /// - `relative_deadline`: the deadline relative to the arrival time
+    /// - `worker_capacity`: the capacity of a serverless worker, in operations/s
+    pub fn set_deadline(&mut self, relative_deadline: RelativeDeadline, worker_capacity: u64) {
+        let relative = match relative_deadline {
+            RelativeDeadline::Fixed(relative) => relative,
+            RelativeDeadline::ServiceTimeMultiple(multiple) => {
+                (self.expected_service_time(worker_capacity) as f64 * multiple).round() as u64
             }
-            JobStatus::Completed => {
-                return None;
+        };
+        self.deadline = Some(self.time_arrival + relative);
+    }
+
+    /// Decide whether the job stops before its next iteration, if it has
+    /// a probability of stopping after every iteration: in this case, the
+    /// stages of the iterations left are dropped, until the post-processing.
+    fn draw_stop(&mut self) {
+        let Some((hazards, rng)) = &mut self.stop_hazards else {
+            return;
+        };
+        let hazard = hazards[self.completed_quantum_iterations as usize];
+        if hazard <= 0.0 || (hazard < 1.0 && !rng.gen_bool(hazard)) {
+            return;
+        }
+        let last = self.stages.len() - 1;
+        for stage in self.stages.drain(self.next_stage..last) {
+            if let Stage::Quantum(durations) = stage {
+                self.remaining_quantum_time -= durations.iter().sum::<u64>();
             }
+        }
+        let summary = StageSummary::new(&self.stages);
+        self.num_operations_iter =
+            summary.per_iteration(summary.num_operations[Phase::ClassicalIteration as usize]);
+        self.dur_qc_iteration = summary.per_iteration(summary.total_quantum_time);
+        self.num_iterations = summary.num_iterations;
+        self.num_quantum_tasks = summary.num_quantum_tasks;
+    }
+
+    /// Return the tasks of the next stage of the job, to be executed in
+    /// parallel, or None if the job is complete.
+    /// It must be called when the job starts and whenever one of its tasks
+    /// is complete: no tasks are returned until all the tasks of the current
+    /// stage are complete.
+    /// Parameters:
+    /// - `cur_time`: the current time, in ns
+    pub fn next_tasks(&mut self, cur_time: u64) -> Option<Vec<crate::task::Task>> {
+        // wait for the other tasks of the current stage
+        if self.pending_tasks > 1 {
+            self.pending_tasks -= 1;
+            return Some(vec![]);
+        }
+
+        // the phase of the previous stage ends now
+        if let Some((phase, since)) = self.last_phase.take() {
+            self.phase_times[phase] += cur_time - since;
+        }
+        if let Some(Stage::Classical(Phase::ClassicalIteration, _)) =
+            self.stages.get(self.next_stage)
+        {
+            self.draw_stop();
+        }
+        let stage = self.stages.get(self.next_stage)?;
+        self.next_stage += 1;
+        let (phase, task_types) = match stage {
+            Stage::Classical(phase, num_operations) => (
+                *phase,
+                vec![crate::task::TaskType::Classical(*num_operations)],
+            ),
+            Stage::Quantum(durations) => (
+                Phase::QuantumIteration,
+                durations
+                    .iter()
+                    .map(|duration| crate::task::TaskType::Quantum(*duration))
+                    .collect(),
+            ),
         };
-        Some(crate::task::Task {
-            job_id: self.job_id,
-            task_type,
-            start_time: cur_time,
-            last_update: cur_time,
-        })
+        self.pending_tasks = task_types.len();
+        self.last_phase = Some((phase as usize, cur_time));
+        Some(
+            task_types
+                .into_iter()
+                .enumerate()
+                .map(|(branch, task_type)| crate::task::Task {
+                    job_id: self.job_id,
+                    task_type,
+                    start_time: cur_time,
+                    last_update: cur_time,
+                    resource: None,
+                    branch,
+                })
+                .collect(),
+        )
     }
 }
 
-pub struct JobFactory {
-    /// RNG
-    rng: rand::rngs::StdRng,
-    /// Next job ID.
-    next_job_id: u64,
+/// Statistics of the traces of an algorithm: for each element of the trace,
+/// the number of qubits with the minimum, average, and maximum values.
+pub type TraceStats = std::collections::HashMap<String, Vec<(u16, f64, f64, f64)>>;
+
+/// Values read from the traces of an algorithm, indexed by number of qubits.
+#[derive(Default)]
+struct TraceSet {
     /// Possibile number of operations for the preparation phase.
     pre_values: std::collections::HashMap<u16, Vec<u64>>,
     /// Possibile number of operations for the iteration phase.
@@ -105,28 +741,65 @@ pub struct JobFactory {
     dur_qc_values: std::collections::HashMap<u16, Vec<u64>>,
     /// Possibile number of iteration values.
     num_iterations_values: std::collections::HashMap<u16, Vec<u64>>,
+    /// Possible number of quantum tasks per iteration, if needed.
+    quantum_tasks_values: std::collections::HashMap<u16, Vec<u64>>,
+    /// Hazard function of the number of iterations, capped to the maximum,
+    /// if any: the probability of stopping after every iteration.
+    iteration_hazards: std::collections::HashMap<u16, std::sync::Arc<Vec<f64>>>,
+    /// Expected number of iterations left after every iteration, capped to
+    /// the maximum, if any.
+    expected_iterations_left: std::collections::HashMap<u16, std::sync::Arc<Vec<f64>>>,
+}
+
+pub struct JobFactory {
+    /// RNG
+    rng: rand::rngs::StdRng,
+    /// Next job ID.
+    next_job_id: u64,
+    /// Traces of VQE jobs, if needed.
+    vqe: Option<TraceSet>,
+    /// Traces of QAOA jobs, by depth of the circuit, if needed.
+    qaoa: std::collections::BTreeMap<u16, TraceSet>,
+    /// Workflows, by name.
+    workflows: std::collections::HashMap<String, crate::workflow::Workflow>,
+    /// Number of quantum tasks per iteration of VQE and QAOA jobs.
+    quantum_tasks: QuantumTasks,
+    /// Model of the number of iterations of VQE and QAOA jobs.
+    convergence: Convergence,
+    /// True if the policies cannot see the true size of VQE and QAOA jobs.
+    hide_job_sizes: bool,
+    /// Variability of the iterations of VQE and QAOA jobs.
+    iteration_variability: IterationVariability,
 }
 
 concatenate!(Estimator, [Min, min], [Max, max], [Mean, mean]);
 
-impl JobFactory {
-    /// Create a factory of jobs.
+impl TraceSet {
+    /// Read the traces from the files in a given directory.
     /// Parameters:
-    /// - `seed`: pseudo-random number generator seed
+    /// - `input_dir`: the directory containing the trace files
     /// - `target_dur_qc_avg`: target durations, in s, of the quantum iterations
     ///   can be empty for some or all values, in which case there is no
     ///   adjustment of the values read from the trace file
-    pub fn new(
-        seed: u64,
+    /// - `quantum_tasks`: true if the number of quantum tasks per iteration
+    ///   is also read from a trace file
+    /// - `convergence`: the model of the number of iterations
+    fn new(
+        input_dir: &str,
         target_dur_qc_avg: &std::collections::BTreeMap<u16, f64>,
+        quantum_tasks: bool,
+        convergence: Convergence,
     ) -> anyhow::Result<Self> {
-        let input_files = std::collections::HashMap::from([
-            ("pre", "input/pre.csv"),
-            ("iter", "input/cost_time.csv"),
-            ("post", "input/post.csv"),
-            ("dur_qc_values", "input/exec_time.csv"),
-            ("num_iterations", "input/num_iterations.csv"),
+        let mut input_files = std::collections::HashMap::from([
+            ("pre", format!("{}pre.csv", input_dir)),
+            ("iter", format!("{}cost_time.csv", input_dir)),
+            ("post", format!("{}post.csv", input_dir)),
+            ("dur_qc_values", format!("{}exec_time.csv", input_dir)),
+            ("num_iterations", format!("{}num_iterations.csv", input_dir)),
         ]);
+        if quantum_tasks {
+            input_files.insert("quantum_tasks", format!("{}quantum_tasks.csv", input_dir));
+        }
 
         // Check that all the required input files exist.
         let mut non_existing_files = vec![];
@@ -140,13 +813,27 @@ impl JobFactory {
             format!("missing input files: {}", non_existing_files.join(","))
         );
 
-        let pre_values = Self::read_from_file(input_files["pre"], SECOND as f64)?;
-        let iter_values = Self::read_from_file(input_files["iter"], SECOND as f64)?;
-        let post_values = Self::read_from_file(input_files["post"], SECOND as f64)?;
-        let mut dur_qc_values = Self::read_from_file(input_files["dur_qc_values"], SECOND as f64)?;
-        let num_iterations_values = Self::read_from_file(input_files["num_iterations"], 1_f64)?;
+        let pre_values = JobFactory::read_from_file(&input_files["pre"], SECOND as f64)?;
+        let iter_values = JobFactory::read_from_file(&input_files["iter"], SECOND as f64)?;
+        let post_values = JobFactory::read_from_file(&input_files["post"], SECOND as f64)?;
+        let mut dur_qc_values =
+            JobFactory::read_from_file(&input_files["dur_qc_values"], SECOND as f64)?;
+        let num_iterations_values =
+            JobFactory::read_from_file(&input_files["num_iterations"], 1_f64)?;
+        let quantum_tasks_values = match input_files.get("quantum_tasks") {
+            Some(input_filename) => {
+                let values = JobFactory::read_from_file(input_filename, 1_f64)?;
+                anyhow::ensure!(
+                    values.values().flatten().all(|x| *x > 0),
+                    "vanishing number of quantum tasks in file '{}'",
+                    input_filename
+                );
+                values
+            }
+            None => std::collections::HashMap::new(),
+        };
 
-        let dur_qc_stats = Self::single_trace_stats(1.0 / SECOND as f64, &dur_qc_values);
+        let dur_qc_stats = JobFactory::single_trace_stats(1.0 / SECOND as f64, &dur_qc_values);
         for (num_qubits, values) in &mut dur_qc_values {
             let average = dur_qc_stats.iter().find(|x| x.0 == *num_qubits).unwrap().2;
             if let Some(target_average) = target_dur_qc_avg.get(num_qubits) {
@@ -157,36 +844,80 @@ impl JobFactory {
         }
 
         Ok(Self {
-            rng: rand::rngs::StdRng::seed_from_u64(seed),
-            next_job_id: 0,
             pre_values,
             iter_values,
             post_values,
             dur_qc_values,
             num_iterations_values,
-        })
+            quantum_tasks_values,
+            iteration_hazards: std::collections::HashMap::new(),
+            expected_iterations_left: std::collections::HashMap::new(),
+        }
+        .with_fitted_iterations(convergence))
     }
 
-    fn single_trace_stats(
-        multiplier: f64,
-        data: &std::collections::HashMap<u16, Vec<u64>>,
-    ) -> Vec<(u16, f64, f64, f64)> {
-        let mut estimators: std::collections::BTreeMap<u16, Estimator> =
-            std::collections::BTreeMap::new();
-        for (k, values) in data {
-            let estimator = estimators.entry(*k).or_default();
-            for value in values {
-                estimator.add(*value as f64 * multiplier);
+    /// Scale the traces of QAOA, with values per layer of the circuit, to
+    /// a circuit with a given depth: the classical and quantum parts of every
+    /// iteration grow linearly with the depth, since the optimizer updates
+    /// two angles per layer, and so does the number of iterations, since
+    /// that of the evaluations needed by the optimizer to converge grows with
+    /// the number of angles.
+    /// Parameters:
+    /// - `depth`: the depth of the circuit
+    /// - `convergence`: the model of the number of iterations
+    fn with_depth(mut self, depth: u64, convergence: Convergence) -> Self {
+        for values in [
+            &mut self.iter_values,
+            &mut self.dur_qc_values,
+            &mut self.num_iterations_values,
+        ] {
+            for value in values.values_mut().flatten() {
+                *value *= depth;
             }
         }
-        let mut ret = vec![];
-        for (k, estimator) in estimators {
-            ret.push((k, estimator.min(), estimator.mean(), estimator.max()))
+        self.with_fitted_iterations(convergence)
+    }
+
+    /// Fit the hazard function and the expected number of iterations left
+    /// from the trace of the number of iterations.
+    /// Parameters:
+    /// - `convergence`: the model of the number of iterations
+    fn with_fitted_iterations(mut self, convergence: Convergence) -> Self {
+        let max_iterations = match convergence {
+            Convergence::Trace => u64::MAX,
+            Convergence::Hazard(max_iterations) => max_iterations,
+        };
+        for (num_qubits, values) in &self.num_iterations_values {
+            let largest = values.iter().max().unwrap().min(&max_iterations);
+            let mut counts = vec![0_u64; *largest as usize + 1];
+            for value in values {
+                counts[*value.min(&max_iterations) as usize] += 1;
+            }
+
+            // go backwards from the largest number of iterations
+            let mut hazards = vec![0.0; counts.len()];
+            let mut expected = vec![0.0; counts.len()];
+            let mut num_larger = 0;
+            let mut sum_larger = 0;
+            for (iteration, count) in counts.iter().enumerate().rev() {
+                if num_larger > 0 {
+                    expected[iteration] = sum_larger as f64 / num_larger as f64 - iteration as f64;
+                }
+                num_larger += count;
+                sum_larger += count * iteration as u64;
+                if num_larger > 0 {
+                    hazards[iteration] = *count as f64 / num_larger as f64;
+                }
+            }
+            self.iteration_hazards
+                .insert(*num_qubits, std::sync::Arc::new(hazards));
+            self.expected_iterations_left
+                .insert(*num_qubits, std::sync::Arc::new(expected));
         }
-        ret
+        self
     }
 
-    pub fn trace_stats(&self) -> std::collections::HashMap<String, Vec<(u16, f64, f64, f64)>> {
+    fn trace_stats(&self) -> TraceStats {
         let mut ret = std::collections::HashMap::new();
         ret.insert(
             "pre".to_string(),
@@ -206,8 +937,199 @@ impl JobFactory {
         );
         ret.insert(
             "num_iterations".to_string(),
-            JobFactory::single_trace_stats(1.0 as f64, &self.num_iterations_values),
+            JobFactory::single_trace_stats(1.0, &self.num_iterations_values),
         );
+        if !self.quantum_tasks_values.is_empty() {
+            ret.insert(
+                "quantum_tasks".to_string(),
+                JobFactory::single_trace_stats(1.0, &self.quantum_tasks_values),
+            );
+        }
+        ret
+    }
+}
+
+impl JobFactory {
+    /// Create a factory of jobs, reading the traces of the algorithms of the
+    /// given job types: those of VQE from `input/`, those of QAOA, with
+    /// values per layer of the circuit, from `input/qaoa/`, and the
+    /// description of every workflow from `input/workflows/NAME.txt`.
+    /// Parameters:
+    /// - `seed`: pseudo-random number generator seed
+    /// - `job_types`: the job types that will be created
+    /// - `target_dur_qc_avg`: target durations, in s, of the quantum iterations
+    ///   of VQE jobs can be empty for some or all values, in which case there
+    ///   is no adjustment of the values read from the trace file
+    /// - `quantum_tasks`: the number of quantum tasks per iteration of VQE
+    ///   and QAOA jobs, which requires the trace `quantum_tasks.csv` if drawn
+    ///   from a trace
+    /// - `convergence`: the model of the number of iterations of VQE and
+    ///   QAOA jobs
+    /// - `hide_job_sizes`: true if the policies only see the expected number
+    ///   of iterations left of VQE and QAOA jobs, instead of the true one
+    /// - `iteration_variability`: the variability of the iterations of VQE
+    ///   and QAOA jobs
+    pub fn new(
+        seed: u64,
+        job_types: &[JobType],
+        target_dur_qc_avg: &std::collections::BTreeMap<u16, f64>,
+        quantum_tasks: QuantumTasks,
+        convergence: Convergence,
+        hide_job_sizes: bool,
+        iteration_variability: IterationVariability,
+    ) -> anyhow::Result<Self> {
+        let from_trace = quantum_tasks == QuantumTasks::Trace;
+        let vqe = if job_types.iter().any(|x| matches!(x, JobType::Vqe(_))) {
+            Some(TraceSet::new(
+                "input/",
+                target_dur_qc_avg,
+                from_trace,
+                convergence,
+            )?)
+        } else {
+            None
+        };
+        let mut qaoa = std::collections::BTreeMap::new();
+        for job_type in job_types {
+            if let JobType::Qaoa(_, depth) = job_type {
+                if !qaoa.contains_key(depth) {
+                    anyhow::ensure!(
+                        std::path::Path::new("input/qaoa/").is_dir(),
+                        "missing directory of the traces of QAOA jobs: input/qaoa/"
+                    );
+                    qaoa.insert(
+                        *depth,
+                        TraceSet::new(
+                            "input/qaoa/",
+                            &std::collections::BTreeMap::new(),
+                            from_trace,
+                            convergence,
+                        )?
+                        .with_depth(*depth as u64, convergence),
+                    );
+                }
+            }
+        }
+        let mut workflows = std::collections::HashMap::new();
+        for job_type in job_types {
+            if let JobType::Workflow(name, _) = job_type {
+                if !workflows.contains_key(name) {
+                    workflows.insert(
+                        name.clone(),
+                        crate::workflow::Workflow::from_file(&format!(
+                            "input/workflows/{}.txt",
+                            name
+                        ))?,
+                    );
+                }
+            }
+        }
+
+        Ok(Self {
+            rng: rand::rngs::StdRng::seed_from_u64(seed),
+            next_job_id: 0,
+            vqe,
+            qaoa,
+            workflows,
+            quantum_tasks,
+            convergence,
+            hide_job_sizes,
+            iteration_variability,
+        })
+    }
+
+    /// Create a factory of VQE jobs with 4 qubits whose phases have fixed
+    /// values, for unit tests only.
+    /// Parameters:
+    /// - `pre`, `iter`, `post`: durations, in s, of the classical phases
+    ///   on a serverless worker with a capacity of 1 G operations/s
+    /// - `dur_qc`: duration, in s, of the quantum iterations
+    /// - `num_iterations`: number of iterations
+    #[cfg(test)]
+    pub(crate) fn for_testing(
+        pre: f64,
+        iter: f64,
+        post: f64,
+        dur_qc: f64,
+        num_iterations: u64,
+    ) -> Self {
+        let values = |value: f64, multiplier: f64| {
+            std::collections::HashMap::from([(4, vec![(value * multiplier).round() as u64])])
+        };
+        Self {
+            rng: rand::rngs::StdRng::seed_from_u64(0),
+            next_job_id: 0,
+            vqe: Some(TraceSet {
+                pre_values: values(pre, SECOND as f64),
+                iter_values: values(iter, SECOND as f64),
+                post_values: values(post, SECOND as f64),
+                dur_qc_values: values(dur_qc, SECOND as f64),
+                num_iterations_values: values(num_iterations as f64, 1_f64),
+                quantum_tasks_values: std::collections::HashMap::new(),
+                iteration_hazards: std::collections::HashMap::new(),
+                expected_iterations_left: std::collections::HashMap::new(),
+            }),
+            qaoa: std::collections::BTreeMap::new(),
+            workflows: std::collections::HashMap::new(),
+            quantum_tasks: QuantumTasks::Fixed(1),
+            convergence: Convergence::Trace,
+            hide_job_sizes: false,
+            iteration_variability: IterationVariability::Fixed,
+        }
+    }
+
+    /// Create a factory of jobs executing a given workflow, for unit tests
+    /// only.
+    /// Parameters:
+    /// - `name`: the name of the workflow
+    /// - `description`: the description of the workflow, as in its file
+    #[cfg(test)]
+    pub(crate) fn for_testing_workflow(name: &str, description: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            rng: rand::rngs::StdRng::seed_from_u64(0),
+            next_job_id: 0,
+            vqe: None,
+            qaoa: std::collections::BTreeMap::new(),
+            workflows: std::collections::HashMap::from([(
+                name.to_string(),
+                description.parse::<crate::workflow::Workflow>()?,
+            )]),
+            quantum_tasks: QuantumTasks::Fixed(1),
+            convergence: Convergence::Trace,
+            hide_job_sizes: false,
+            iteration_variability: IterationVariability::Fixed,
+        })
+    }
+
+    fn single_trace_stats(
+        multiplier: f64,
+        data: &std::collections::HashMap<u16, Vec<u64>>,
+    ) -> Vec<(u16, f64, f64, f64)> {
+        let mut estimators: std::collections::BTreeMap<u16, Estimator> =
+            std::collections::BTreeMap::new();
+        for (k, values) in data {
+            let estimator = estimators.entry(*k).or_default();
+            for value in values {
+                estimator.add(*value as f64 * multiplier);
+            }
+        }
+        let mut ret = vec![];
+        for (k, estimator) in estimators {
+            ret.push((k, estimator.min(), estimator.mean(), estimator.max()))
+        }
+        ret
+    }
+
+    /// Return the statistics of the traces read, per algorithm and, with
+    /// QAOA, depth of the circuit.
+    pub fn trace_stats(&self) -> std::collections::BTreeMap<String, TraceStats> {
+        let mut ret = std::collections::BTreeMap::new();
+        if let Some(traces) = &self.vqe {
+            ret.insert("VQE".to_string(), traces.trace_stats());
+        }
+        for (depth, traces) in &self.qaoa {
+            ret.insert(format!("QAOA:{}", depth), traces.trace_stats());
+        }
         ret
     }
 
@@ -240,6 +1162,11 @@ impl JobFactory {
     }
 
     /// Create a new random job.
+    /// VQE and QAOA jobs are made of a preparation phase, a number of
+    /// iterations, each with a classical and a quantum task, and
+    /// a post-processing phase, while workflow jobs are made of the stages
+    /// drawn from their description.
+    /// With QAOA, the traces are those scaled to the depth of the circuit.
     /// Parameters:
     /// - `job_type`: the job type
     /// - `priority`: the job priority
@@ -253,66 +1180,149 @@ impl JobFactory {
         let id = self.next_job_id;
         self.next_job_id += 1;
 
-        match job_type {
-            JobType::Vqe(num_qubits) => {
-                let num_operations_pre = if let Some(values) = self.pre_values.get(&num_qubits) {
-                    values.choose(&mut self.rng).unwrap()
-                } else {
-                    anyhow::bail!(
-                        "number of qubits not found in preparation phase trace: {}",
-                        num_qubits
-                    )
+        let traces = match &job_type {
+            JobType::Vqe(_) => self.vqe.as_ref(),
+            JobType::Qaoa(_, depth) => self.qaoa.get(depth),
+            JobType::Workflow(name, _) => {
+                let Some(workflow) = self.workflows.get(name) else {
+                    anyhow::bail!("no description for workflow: {}", name)
                 };
-                let num_operations_iter = if let Some(values) = self.iter_values.get(&num_qubits) {
-                    values.choose(&mut self.rng).unwrap()
-                } else {
-                    anyhow::bail!(
-                        "number of qubits not found in classical iteration trace: {}",
-                        num_qubits
-                    )
-                };
-                let num_operations_post = if let Some(values) = self.post_values.get(&num_qubits) {
-                    values.choose(&mut self.rng).unwrap()
+                let stages = workflow.stages(&mut self.rng);
+                return Ok(Job::new(job_type, id, priority, time_arrival, stages));
+            }
+        };
+        let Some(traces) = traces else {
+            anyhow::bail!("no traces for job type: {}", job_type.algorithm())
+        };
+        let num_qubits = job_type.num_qubits();
+
+        let num_operations_pre = if let Some(values) = traces.pre_values.get(&num_qubits) {
+            values.choose(&mut self.rng).unwrap()
+        } else {
+            anyhow::bail!(
+                "number of qubits not found in preparation phase trace: {}",
+                num_qubits
+            )
+        };
+        let num_operations_iter = if let Some(values) = traces.iter_values.get(&num_qubits) {
+            values.choose(&mut self.rng).unwrap()
+        } else {
+            anyhow::bail!(
+                "number of qubits not found in classical iteration trace: {}",
+                num_qubits
+            )
+        };
+        let num_operations_post = if let Some(values) = traces.post_values.get(&num_qubits) {
+            values.choose(&mut self.rng).unwrap()
+        } else {
+            anyhow::bail!(
+                "number of qubits not found in post-processing phase trace: {}",
+                num_qubits
+            )
+        };
+        let dur_qc_iteration = if let Some(values) = traces.dur_qc_values.get(&num_qubits) {
+            values.choose(&mut self.rng).unwrap()
+        } else {
+            anyhow::bail!(
+                "number of qubits not found in QC execution trace: {}",
+                num_qubits
+            )
+        };
+        let num_iterations = match (
+            self.convergence,
+            traces.num_iterations_values.get(&num_qubits),
+        ) {
+            (Convergence::Trace, Some(values)) => *values.choose(&mut self.rng).unwrap(),
+            (Convergence::Hazard(_), Some(_)) => {
+                // the job may execute up to the largest number of iterations,
+                // but it decides whether to stop after every iteration
+                traces.iteration_hazards[&num_qubits].len() as u64 - 1
+            }
+            (_, None) => anyhow::bail!(
+                "number of qubits not found in number of iterations trace: {}",
+                num_qubits
+            ),
+        };
+        let num_quantum_tasks = match self.quantum_tasks {
+            QuantumTasks::Fixed(value) => value,
+            QuantumTasks::Power(coeff, exponent) => {
+                ((coeff * (num_qubits as f64).powf(exponent)).round() as u64).max(1)
+            }
+            QuantumTasks::Trace => {
+                if let Some(values) = traces.quantum_tasks_values.get(&num_qubits) {
+                    *values.choose(&mut self.rng).unwrap()
                 } else {
                     anyhow::bail!(
-                        "number of qubits not found in post-processing phase trace: {}",
+                        "number of qubits not found in quantum tasks trace: {}",
                         num_qubits
                     )
-                };
-                let dur_qc_iteration = if let Some(values) = self.dur_qc_values.get(&num_qubits) {
-                    values.choose(&mut self.rng).unwrap()
-                } else {
-                    anyhow::bail!(
-                        "number of qubits not found in QC execution trace: {}",
-                        num_qubits
+                }
+            }
+        };
+
+        let iterations = match self.iteration_variability {
+            IterationVariability::Fixed => {
+                vec![(*num_operations_iter, *dur_qc_iteration); num_iterations as usize]
+            }
+            IterationVariability::Resample => (0..num_iterations)
+                .map(|_| {
+                    (
+                        *traces.iter_values[&num_qubits]
+                            .choose(&mut self.rng)
+                            .unwrap(),
+                        *traces.dur_qc_values[&num_qubits]
+                            .choose(&mut self.rng)
+                            .unwrap(),
                     )
-                };
-                let num_iterations =
-                    if let Some(values) = self.num_iterations_values.get(&num_qubits) {
-                        values.choose(&mut self.rng).unwrap()
-                    } else {
-                        anyhow::bail!(
-                            "number of qubits not found in number of iterations trace: {}",
-                            num_qubits
-                        )
-                    };
-
-                Ok(Job {
-                    job_type,
-                    job_status: JobStatus::Preparation,
-                    job_id: id,
-                    num_operations_pre: *num_operations_pre,
-                    num_operations_iter: *num_operations_iter,
-                    num_operations_post: *num_operations_post,
-                    dur_qc_iteration: *dur_qc_iteration,
-                    num_iterations: *num_iterations,
-                    time_arrival,
-                    num_qubits,
-                    priority,
-                    label: format!("{},{}", num_qubits, priority),
                 })
+                .collect(),
+            IterationVariability::Ar1(correlation, stddev) => {
+                // start from the stationary distribution of the process
+                let normal = rand_distr::Normal::new(0.0, stddev).unwrap();
+                let innovation = (1.0 - correlation * correlation).sqrt();
+                let mut logs = [normal.sample(&mut self.rng), normal.sample(&mut self.rng)];
+                let scale = |value: u64, log: f64| {
+                    (value as f64 * (log - stddev * stddev / 2.0).exp()).round() as u64
+                };
+                let mut iterations = Vec::with_capacity(num_iterations as usize);
+                for _ in 0..num_iterations {
+                    iterations.push((
+                        scale(*num_operations_iter, logs[0]),
+                        scale(*dur_qc_iteration, logs[1]),
+                    ));
+                    for log in &mut logs {
+                        *log = correlation * *log + innovation * normal.sample(&mut self.rng);
+                    }
+                }
+                iterations
             }
+        };
+
+        let mut job = Job::new(
+            job_type,
+            id,
+            priority,
+            time_arrival,
+            pipeline(
+                *num_operations_pre,
+                &iterations,
+                *num_operations_post,
+                num_quantum_tasks,
+            ),
+        );
+        if let Convergence::Hazard(_) = self.convergence {
+            job.stop_hazards = Some((
+                traces.iteration_hazards[&num_qubits].clone(),
+                rand::rngs::StdRng::seed_from_u64(self.rng.gen()),
+            ));
+        }
+        // the number of iterations is unknown until the job stops with
+        // a hazard convergence, hence the policies cannot see it
+        if self.hide_job_sizes || job.stop_hazards.is_some() {
+            job.expected_iterations_left =
+                Some(traces.expected_iterations_left[&num_qubits].clone());
         }
+        Ok(job)
     }
 }
 
@@ -320,9 +1330,34 @@ impl JobFactory {
 mod tests {
     use super::*;
 
+    /// Return a factory of jobs with the given traces of VQE, if any, and
+    /// the default settings, which the tests override as needed.
+    fn test_factory(vqe: Option<TraceSet>) -> JobFactory {
+        JobFactory {
+            rng: rand::rngs::StdRng::seed_from_u64(42),
+            next_job_id: 0,
+            vqe,
+            qaoa: std::collections::BTreeMap::new(),
+            workflows: std::collections::HashMap::new(),
+            quantum_tasks: QuantumTasks::Fixed(1),
+            convergence: Convergence::Trace,
+            hide_job_sizes: false,
+            iteration_variability: IterationVariability::Fixed,
+        }
+    }
+
     #[test]
     fn test_job_factory() -> anyhow::Result<()> {
-        let mut jf = JobFactory::new(42, &std::collections::BTreeMap::new()).unwrap();
+        let mut jf = JobFactory::new(
+            42,
+            &[JobType::Vqe(4)],
+            &std::collections::BTreeMap::new(),
+            QuantumTasks::Fixed(1),
+            Convergence::Trace,
+            false,
+            IterationVariability::Fixed,
+        )
+        .unwrap();
         let num_qubits_choices = vec![4, 6, 8, 10];
         let mut id = 0;
         for i in 0..10 {
@@ -345,6 +1380,442 @@ mod tests {
         }
 
         assert!(jf.make(JobType::Vqe(999), 0, 0).is_err());
+        assert!(jf.make(JobType::Qaoa(4, 1), 0, 0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_job_types() -> anyhow::Result<()> {
+        assert_eq!(
+            vec![JobType::Vqe(4), JobType::Vqe(8)],
+            parse_job_types("VQE;4;8")?
+        );
+        assert_eq!(
+            vec![
+                JobType::Vqe(4),
+                JobType::Qaoa(6, 1),
+                JobType::Qaoa(10, 1),
+                JobType::Qaoa(6, 3)
+            ],
+            parse_job_types("vqe;4|QAOA;6;10|qaoa:3;6")?
+        );
+        assert_eq!("QAOA:3,6,2", JobType::Qaoa(6, 3).label(2));
+
+        assert_eq!(
+            vec![
+                JobType::Workflow("knitting".to_string(), 8),
+                JobType::Vqe(4)
+            ],
+            parse_job_types("WORKFLOW:knitting;8|VQE;4")?
+        );
+        assert_eq!(
+            "WORKFLOW:zne,4,1",
+            JobType::Workflow("zne".to_string(), 4).label(1)
+        );
+
+        for invalid in [
+            "",
+            "VQE",
+            "VQE;x",
+            "VQE:2;4",
+            "QAOA:0;4",
+            "QAOA:x;4",
+            "VQE;4|",
+            "GHZ;4",
+            "WORKFLOW;4",
+            "WORKFLOW:;4",
+        ] {
+            assert!(parse_job_types(invalid).is_err(), "{}", invalid);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_qaoa_job() -> anyhow::Result<()> {
+        let values = |value: u64| std::collections::HashMap::from([(6, vec![value])]);
+        let mut jf = JobFactory {
+            qaoa: std::collections::BTreeMap::from([(
+                3,
+                TraceSet {
+                    pre_values: values(SECOND),
+                    iter_values: values(MILLISECOND),
+                    post_values: values(2 * SECOND),
+                    dur_qc_values: values(100 * MILLISECOND),
+                    num_iterations_values: values(50),
+                    ..Default::default()
+                }
+                .with_depth(3, Convergence::Trace),
+            )]),
+            ..test_factory(None)
+        };
+
+        // the classical and quantum iterations, and their number, grow with
+        // the depth
+        let job = jf.make(JobType::Qaoa(6, 3), 1, 0)?;
+        assert_eq!(SECOND, job.num_operations_pre);
+        assert_eq!(3 * MILLISECOND, job.num_operations_iter);
+        assert_eq!(2 * SECOND, job.num_operations_post);
+        assert_eq!(300 * MILLISECOND, job.dur_qc_iteration);
+        assert_eq!(150, job.num_iterations);
+        assert_eq!(6, job.num_qubits);
+        assert_eq!("QAOA:3,6,1", job.label);
+
+        assert!(jf.make(JobType::Vqe(6), 1, 0).is_err());
+        assert!(jf.make(JobType::Qaoa(6, 1), 1, 0).is_err());
+        assert!(jf.make(JobType::Qaoa(4, 3), 1, 0).is_err());
+
+        // the traces of QAOA are required
+        let res = JobFactory::new(
+            42,
+            &[JobType::Qaoa(6, 1)],
+            &std::collections::BTreeMap::new(),
+            QuantumTasks::Fixed(1),
+            Convergence::Trace,
+            false,
+            IterationVariability::Fixed,
+        );
+        assert!(res.is_err_and(|err| err.to_string().contains("input/qaoa/")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_quantum_tasks() -> anyhow::Result<()> {
+        use std::str::FromStr;
+
+        assert_eq!(QuantumTasks::Fixed(3), QuantumTasks::from_str("3")?);
+        assert_eq!(
+            QuantumTasks::Power(0.5, 2.0),
+            QuantumTasks::from_str("pow:0.5:2")?
+        );
+        assert_eq!(QuantumTasks::Trace, QuantumTasks::from_str("trace")?);
+        for invalid in ["", "0", "x", "pow:0:2", "pow:1", "pow:1:x", "trace:1"] {
+            assert!(QuantumTasks::from_str(invalid).is_err(), "{}", invalid);
+        }
+
+        let values = |value: u64| std::collections::HashMap::from([(4, vec![value])]);
+        let mut jf = JobFactory {
+            quantum_tasks: QuantumTasks::Power(0.25, 2.0),
+            ..test_factory(Some(TraceSet {
+                pre_values: values(SECOND),
+                iter_values: values(MILLISECOND),
+                post_values: values(SECOND),
+                dur_qc_values: values(1_000_000_001),
+                num_iterations_values: values(10),
+                quantum_tasks_values: values(2),
+                ..Default::default()
+            }))
+        };
+
+        // the duration of an iteration is split evenly among its tasks
+        let stage = |job: &Job| job.stages[2].clone();
+        let job = jf.make(JobType::Vqe(4), 1, 0)?;
+        assert_eq!(
+            Stage::Quantum(vec![250_000_001, 250_000_000, 250_000_000, 250_000_000]),
+            stage(&job)
+        );
+        assert_eq!(1_000_000_001, job.dur_qc_iteration);
+        assert_eq!(10, job.num_iterations);
+        assert_eq!(
+            2_010 * MILLISECOND + 10 * 250_000_001,
+            job.expected_service_time(SECOND)
+        );
+
+        jf.quantum_tasks = QuantumTasks::Trace;
+        let job = jf.make(JobType::Vqe(4), 1, 0)?;
+        assert_eq!(Stage::Quantum(vec![500_000_001, 500_000_000]), stage(&job));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convergence() -> anyhow::Result<()> {
+        use std::str::FromStr;
+
+        assert_eq!(Convergence::Trace, Convergence::from_str("trace")?);
+        assert_eq!(Convergence::Hazard(5), Convergence::from_str("hazard:5")?);
+        for invalid in ["", "hazard", "hazard:0", "hazard:x", "trace:1"] {
+            assert!(Convergence::from_str(invalid).is_err(), "{}", invalid);
+        }
+
+        let values = |value: u64| std::collections::HashMap::from([(4, vec![value])]);
+        let traces = |convergence| {
+            TraceSet {
+                pre_values: values(SECOND),
+                iter_values: values(MILLISECOND),
+                post_values: values(SECOND),
+                dur_qc_values: values(100 * MILLISECOND),
+                num_iterations_values: std::collections::HashMap::from([(4, vec![2, 2, 4, 6])]),
+                ..Default::default()
+            }
+            .with_fitted_iterations(convergence)
+        };
+
+        let fitted = traces(Convergence::Trace);
+        assert_eq!(
+            vec![0.0, 0.0, 0.5, 0.0, 0.5, 0.0, 1.0],
+            *fitted.iteration_hazards[&4]
+        );
+        assert_eq!(
+            vec![3.5, 2.5, 3.0, 2.0, 2.0, 1.0, 0.0],
+            *fitted.expected_iterations_left[&4]
+        );
+        let fitted = traces(Convergence::Hazard(5));
+        assert_eq!(
+            vec![0.0, 0.0, 0.5, 0.0, 0.5, 1.0],
+            *fitted.iteration_hazards[&4]
+        );
+        assert_eq!(
+            vec![3.25, 2.25, 2.5, 1.5, 1.0, 0.0],
+            *fitted.expected_iterations_left[&4]
+        );
+
+        // the decision to stop is drawn after every iteration, hence a job
+        // can execute up to the maximum number of iterations until it stops,
+        // and the number of iterations follows the trace, up to the maximum
+        let mut jf = JobFactory {
+            convergence: Convergence::Hazard(5),
+            ..test_factory(Some(fitted))
+        };
+        let mut num_iterations = std::collections::BTreeMap::new();
+        for _ in 0..1000 {
+            let mut job = jf.make(JobType::Vqe(4), 1, 0)?;
+            let mut tasks = job.next_tasks(0).unwrap();
+            while let Some(task) = tasks.pop() {
+                if let crate::task::TaskType::Quantum(duration) = task.task_type {
+                    job.quantum_task_completed(duration);
+                }
+                let stopped = job.num_iterations < 5;
+                assert!(!stopped || job.num_iterations == job.completed_quantum_iterations);
+                tasks = job.next_tasks(0).unwrap_or_default();
+                if let Some(crate::task::TaskType::Quantum(_)) =
+                    tasks.first().map(|task| task.task_type)
+                {
+                    assert!(!stopped);
+                }
+            }
+            *num_iterations.entry(job.num_iterations).or_insert(0) += 1;
+        }
+        assert_eq!(
+            vec![2, 4, 5],
+            num_iterations.keys().cloned().collect::<Vec<_>>()
+        );
+        assert!((450..550).contains(&num_iterations[&2]));
+        assert!((200..300).contains(&num_iterations[&4]));
+
+        // the policies see the expected iterations left
+        let mut job = jf.make(JobType::Vqe(4), 1, 0)?;
+        assert_eq!(5, job.num_iterations);
+        assert_eq!(325 * MILLISECOND, job.remaining_quantum_time());
+        assert_eq!(
+            2 * SECOND + 3_250 * MILLISECOND / 10 + 3_250 * MILLISECOND / 1000,
+            job.expected_service_time(SECOND)
+        );
+        for _ in 0..2 {
+            job.quantum_task_completed(100 * MILLISECOND);
+        }
+        assert_eq!(250 * MILLISECOND, job.remaining_quantum_time());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_iteration_variability() -> anyhow::Result<()> {
+        use std::str::FromStr;
+
+        assert_eq!(
+            IterationVariability::Fixed,
+            IterationVariability::from_str("fixed")?
+        );
+        assert_eq!(
+            IterationVariability::Resample,
+            IterationVariability::from_str("resample")?
+        );
+        assert_eq!(
+            IterationVariability::Ar1(0.9, 0.5),
+            IterationVariability::from_str("ar1:0.9:0.5")?
+        );
+        for invalid in ["", "ar1", "ar1:0.9", "ar1:1:0.5", "ar1:0.9:-1", "ar1:x:1"] {
+            assert!(
+                IterationVariability::from_str(invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+
+        let values = |values: Vec<u64>| std::collections::HashMap::from([(4, values)]);
+        let mut jf = test_factory(Some(TraceSet {
+            pre_values: values(vec![SECOND]),
+            iter_values: values(vec![MILLISECOND, 2 * MILLISECOND]),
+            post_values: values(vec![SECOND]),
+            dur_qc_values: values(vec![100 * MILLISECOND, 200 * MILLISECOND]),
+            num_iterations_values: values(vec![1000]),
+            ..Default::default()
+        }));
+        let iterations = |job: &Job| {
+            job.stages[1..]
+                .chunks(2)
+                .filter_map(|stages| match stages {
+                    [Stage::Classical(_, num_operations), Stage::Quantum(durations)] => {
+                        Some((*num_operations, durations[0]))
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<(u64, u64)>>()
+        };
+        let distinct = |job: &Job| {
+            iterations(job)
+                .into_iter()
+                .collect::<std::collections::BTreeSet<(u64, u64)>>()
+                .len()
+        };
+
+        let job = jf.make(JobType::Vqe(4), 1, 0)?;
+        assert_eq!(1000, iterations(&job).len());
+        assert_eq!(1, distinct(&job));
+
+        // all the combinations of values are drawn
+        jf.iteration_variability = IterationVariability::Resample;
+        let job = jf.make(JobType::Vqe(4), 1, 0)?;
+        assert_eq!(4, distinct(&job));
+        assert!(job.dur_qc_iteration > 140 * MILLISECOND);
+        assert!(job.dur_qc_iteration < 160 * MILLISECOND);
+
+        // no variability without deviation
+        jf.iteration_variability = IterationVariability::Ar1(0.9, 0.0);
+        let job = jf.make(JobType::Vqe(4), 1, 0)?;
+        assert_eq!(1, distinct(&job));
+
+        // the factors have unit mean and consecutive ones are correlated
+        let vqe = jf.vqe.as_mut().unwrap();
+        vqe.iter_values = values(vec![MILLISECOND]);
+        vqe.dur_qc_values = values(vec![100 * MILLISECOND]);
+        jf.iteration_variability = IterationVariability::Ar1(0.9, 0.5);
+        let job = jf.make(JobType::Vqe(4), 1, 0)?;
+        assert!(job.num_operations_iter > 750_000);
+        assert!(job.num_operations_iter < 1_250_000);
+        assert!(job.dur_qc_iteration > 75 * MILLISECOND);
+        assert!(job.dur_qc_iteration < 125 * MILLISECOND);
+        let durations = iterations(&job)
+            .into_iter()
+            .map(|(_, duration)| duration as f64)
+            .collect::<Vec<f64>>();
+        let mean = durations.iter().sum::<f64>() / durations.len() as f64;
+        let covariance = |lag: usize| {
+            durations
+                .iter()
+                .zip(durations.iter().skip(lag))
+                .map(|(x, y)| (x - mean) * (y - mean))
+                .sum::<f64>()
+        };
+        assert!(covariance(1) > 0.5 * covariance(0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_quantum_tasks() -> anyhow::Result<()> {
+        let mut job = Job::new(
+            JobType::Workflow("test".to_string(), 4),
+            0,
+            1,
+            0,
+            vec![
+                Stage::Classical(Phase::Preparation, SECOND),
+                Stage::Quantum(vec![SECOND, 3 * SECOND]),
+                Stage::Classical(Phase::Postprocessing, SECOND),
+            ],
+        );
+        assert_eq!(5 * SECOND, job.expected_service_time(SECOND));
+        assert_eq!(4 * SECOND, job.remaining_quantum_time());
+
+        assert_eq!(1, job.next_tasks(0).unwrap().len());
+        let tasks = job.next_tasks(SECOND).unwrap();
+        assert_eq!(
+            vec![0, 1],
+            tasks.iter().map(|x| x.branch).collect::<Vec<_>>()
+        );
+        assert_eq!(3 * SECOND, job.quantum_iteration_duration(1));
+
+        // the next stage starts only after both quantum tasks are complete
+        assert!(!job.quantum_task_completed(3 * SECOND));
+        assert!(job.has_quantum_iterations_left());
+        assert!(job.next_tasks(4 * SECOND).unwrap().is_empty());
+        assert!(job.quantum_task_completed(SECOND));
+        assert!(!job.has_quantum_iterations_left());
+        assert_eq!(0, job.remaining_quantum_time());
+        let tasks = job.next_tasks(5 * SECOND).unwrap();
+        assert!(matches!(
+            tasks[0].task_type,
+            crate::task::TaskType::Classical(SECOND)
+        ));
+        assert!(job.next_tasks(6 * SECOND).is_none());
+        assert_eq!([1, 0, 4, 1].map(|x| x * SECOND), job.phase_times);
+        assert_eq!(1, job.num_iterations);
+        assert_eq!(4 * SECOND, job.dur_qc_iteration);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_job_deadline() -> anyhow::Result<()> {
+        use std::str::FromStr;
+
+        assert_eq!(
+            RelativeDeadline::Fixed(120 * SECOND),
+            RelativeDeadline::from_str("120")?
+        );
+        assert_eq!(
+            RelativeDeadline::ServiceTimeMultiple(2.5),
+            RelativeDeadline::from_str("x2.5")?
+        );
+        for invalid in ["", "x", "x0", "-1", "abc"] {
+            assert!(RelativeDeadline::from_str(invalid).is_err());
+        }
+
+        // 1 s + 10 x (1 ms + 1 s) + 1 s
+        let mut job = Job::for_testing(0, 4, 1);
+        job.time_arrival = 100 * SECOND;
+        assert_eq!(12_010 * MILLISECOND, job.expected_service_time(SECOND));
+        assert_eq!(11_005 * MILLISECOND, job.expected_service_time(2 * SECOND));
+        assert_eq!(2_010 * MILLISECOND, job.classical_service_time(SECOND));
+
+        job.set_deadline(RelativeDeadline::Fixed(60 * SECOND), SECOND);
+        assert_eq!(Some(160 * SECOND), job.deadline);
+        job.set_deadline(RelativeDeadline::ServiceTimeMultiple(2.0), SECOND);
+        assert_eq!(Some(124_020 * MILLISECOND), job.deadline);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_job_record() -> anyhow::Result<()> {
+        let mut job = Job::new(
+            JobType::Vqe(4),
+            7,
+            2,
+            0,
+            pipeline(SECOND, &[(MILLISECOND, SECOND); 2], SECOND, 1),
+        );
+
+        // preparation, 2 x (classical + quantum iteration), post-processing
+        let mut now = 0;
+        for duration in [5, 1, 3, 1, 3, 2] {
+            assert_eq!(1, job.next_tasks(now * SECOND).unwrap().len());
+            now += duration;
+        }
+        assert!(job.next_tasks(now * SECOND).is_none());
+        assert_eq!([5, 2, 6, 2].map(|x| x * SECOND), job.phase_times);
+
+        let record = job.to_csv(now * SECOND);
+        assert_eq!(
+            Job::header().split(',').count(),
+            record.split(',').count() - LABEL_HEADER.matches(',').count()
+        );
+        assert!(record.starts_with("7,\"VQE,4,2\",VQE,4,2,0,15,2,"));
+        assert!(record.ends_with(",0,5,2,6,2"));
 
         Ok(())
     }
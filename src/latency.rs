@@ -0,0 +1,229 @@
+// SPDX-FileCopyrightText: © 2024 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+use rand_distr::Distribution;
+
+/// Distribution of a random latency.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LatencyDistribution {
+    /// Always the same value, in ns.
+    Constant(u64),
+    /// Exponentially distributed, with given mean, in ns.
+    Exponential(f64),
+    /// Uniformly distributed between a minimum and a maximum, in ns.
+    Uniform(u64, u64),
+}
+
+impl LatencyDistribution {
+    /// Draw a random latency, in ns.
+    pub fn sample(&self, rng: &mut rand::rngs::StdRng) -> u64 {
+        match self {
+            Self::Constant(value) => *value,
+            Self::Exponential(mean) => rand_distr::Exp::new(1.0 / mean)
+                .unwrap()
+                .sample(rng)
+                .round() as u64,
+            Self::Uniform(min, max) => rand_distr::Uniform::new_inclusive(min, max).sample(rng),
+        }
+    }
+
+    /// Return the mean latency, in ns.
+    pub fn mean(&self) -> f64 {
+        match self {
+            Self::Constant(value) => *value as f64,
+            Self::Exponential(mean) => *mean,
+            Self::Uniform(min, max) => (*min + *max) as f64 / 2.0,
+        }
+    }
+}
+
+impl std::str::FromStr for LatencyDistribution {
+    type Err = anyhow::Error;
+    /// Parse a latency distribution, with values in s, specified as one of:
+    /// `VALUE` (constant), `exp:MEAN`, `uniform:MIN:MAX`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = s.split(':').collect::<Vec<&str>>();
+        let values = tokens[1..]
+            .iter()
+            .map(|x| x.parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| anyhow::anyhow!("invalid latency distribution: {}", s))?;
+        anyhow::ensure!(
+            values.iter().all(|x| *x >= 0.0),
+            "negative values in latency distribution: {}",
+            s
+        );
+        let to_ns = |x: f64| (x * 1e9).round() as u64;
+        match (tokens[0], values.len()) {
+            ("exp", 1) => {
+                anyhow::ensure!(values[0] > 0.0, "vanishing mean latency: {}", s);
+                Ok(Self::Exponential(values[0] * 1e9))
+            }
+            ("uniform", 2) => {
+                anyhow::ensure!(values[0] <= values[1], "invalid uniform latency: {}", s);
+                Ok(Self::Uniform(to_ns(values[0]), to_ns(values[1])))
+            }
+            (value, 0) => match value.parse::<f64>() {
+                Ok(value) if value >= 0.0 => Ok(Self::Constant(to_ns(value))),
+                _ => anyhow::bail!("invalid latency distribution: {}", s),
+            },
+            _ => anyhow::bail!("invalid latency distribution: {}", s),
+        }
+    }
+}
+
+/// Model of the hand-offs between the classical and quantum stages of a job,
+/// which is executed by a remote quantum computer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NetworkModel {
+    /// The latency to submit a quantum task, including the queueing
+    /// overhead at the provider.
+    submission: LatencyDistribution,
+    /// The latency to retrieve the result of a quantum task.
+    retrieval: LatencyDistribution,
+    /// The size of the payload submitted, in bytes.
+    request_size: u64,
+    /// The size of the result retrieved, in bytes.
+    response_size: u64,
+    /// The bandwidth of the link, in b/s.
+    bandwidth: f64,
+}
+
+impl NetworkModel {
+    /// Create a network model.
+    /// Parameters:
+    /// - `submission`: the latency distribution of the submission of a
+    ///   quantum task (empty means no latency)
+    /// - `retrieval`: the latency distribution of the retrieval of the
+    ///   result of a quantum task (empty means no latency)
+    /// - `payload`: the sizes of the request and response, in bytes, as
+    ///   `REQUEST:RESPONSE` (empty means no payload)
+    /// - `bandwidth`: the bandwidth of the link, in Mb/s
+    ///
+    /// Return None if all the hand-offs are instantaneous.
+    pub fn new(
+        submission: &str,
+        retrieval: &str,
+        payload: &str,
+        bandwidth: f64,
+    ) -> anyhow::Result<Option<Self>> {
+        if submission.is_empty() && retrieval.is_empty() && payload.is_empty() {
+            return Ok(None);
+        }
+        let parse_latency = |s: &str| {
+            if s.is_empty() {
+                Ok(LatencyDistribution::Constant(0))
+            } else {
+                s.parse::<LatencyDistribution>()
+            }
+        };
+        let (request_size, response_size) = if payload.is_empty() {
+            (0, 0)
+        } else {
+            let (request, response) = payload
+                .split_once(':')
+                .ok_or(anyhow::anyhow!("invalid payload sizes: {}", payload))?;
+            (
+                request
+                    .parse::<u64>()
+                    .map_err(|_| anyhow::anyhow!("invalid request size: {}", payload))?,
+                response
+                    .parse::<u64>()
+                    .map_err(|_| anyhow::anyhow!("invalid response size: {}", payload))?,
+            )
+        };
+        anyhow::ensure!(bandwidth > 0.0, "vanishing bandwidth");
+        Ok(Some(Self {
+            submission: parse_latency(submission)?,
+            retrieval: parse_latency(retrieval)?,
+            request_size,
+            response_size,
+            bandwidth: bandwidth * 1e6,
+        }))
+    }
+
+    /// Draw the time to submit a quantum task, in ns.
+    pub fn submission_delay(&self, rng: &mut rand::rngs::StdRng) -> u64 {
+        self.submission.sample(rng) + self.transfer_time(self.request_size)
+    }
+
+    /// Draw the time to retrieve the result of a quantum task, in ns.
+    pub fn retrieval_delay(&self, rng: &mut rand::rngs::StdRng) -> u64 {
+        self.retrieval.sample(rng) + self.transfer_time(self.response_size)
+    }
+
+    /// Return the time to transfer a payload on the link, in ns.
+    fn transfer_time(&self, size: u64) -> u64 {
+        (size as f64 * 8.0 / self.bandwidth * 1e9).round() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_distribution() -> anyhow::Result<()> {
+        use rand::SeedableRng;
+        use std::str::FromStr;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+
+        let constant = LatencyDistribution::from_str("0.5")?;
+        assert_eq!(LatencyDistribution::Constant(500_000_000), constant);
+        assert_eq!(500_000_000, constant.sample(&mut rng));
+
+        let uniform = LatencyDistribution::from_str("uniform:1:2")?;
+        assert_eq!(1.5e9, uniform.mean());
+        for _ in 0..100 {
+            let value = uniform.sample(&mut rng);
+            assert!((1_000_000_000..=2_000_000_000).contains(&value));
+        }
+
+        let exponential = LatencyDistribution::from_str("exp:2")?;
+        let num_samples = 10000;
+        let avg = (0..num_samples)
+            .map(|_| exponential.sample(&mut rng) as f64)
+            .sum::<f64>()
+            / num_samples as f64;
+        assert!((avg / exponential.mean() - 1.0).abs() < 0.05, "{}", avg);
+
+        for invalid in ["", "-1", "exp:0", "exp", "uniform:2:1", "normal:1:1", "x"] {
+            assert!(LatencyDistribution::from_str(invalid).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_network_model() -> anyhow::Result<()> {
+        use rand::SeedableRng;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+
+        assert!(NetworkModel::new("", "", "", 100.0)?.is_none());
+
+        let latency_only = NetworkModel::new("0.5", "", "", 100.0)?.unwrap();
+        assert_eq!(500_000_000, latency_only.submission_delay(&mut rng));
+        assert_eq!(0, latency_only.retrieval_delay(&mut rng));
+
+        // 1 MB at 8 Mb/s takes 1 s, 1 kB takes 1 ms
+        let network = NetworkModel::new("0.5", "uniform:0.1:0.2", "1000000:1000", 8.0)?.unwrap();
+        assert_eq!(1_500_000_000, network.submission_delay(&mut rng));
+        for _ in 0..100 {
+            let value = network.retrieval_delay(&mut rng);
+            assert!((101_000_000..=201_000_000).contains(&value));
+        }
+
+        for (submission, payload, bandwidth) in [
+            ("x", "", 100.0),
+            ("", "1000", 100.0),
+            ("", "1000:x", 100.0),
+            ("", "1000:1000", 0.0),
+        ] {
+            assert!(NetworkModel::new(submission, "", payload, bandwidth).is_err());
+        }
+
+        Ok(())
+    }
+}
@@ -1,7 +1,14 @@
 // SPDX-FileCopyrightText: © 2024 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
 // SPDX-License-Identifier: MIT
 
+pub mod autoscaler;
+pub mod classical_scheduler;
 pub mod job;
+pub mod latency;
 pub mod output;
+pub mod quantum_computer;
+pub mod quantum_scheduler;
 pub mod simulation;
 pub mod task;
+pub mod trace;
+pub mod workflow;
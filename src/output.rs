@@ -22,11 +22,24 @@ impl TimeAvg {
     }
 }
 
+#[derive(Default)]
+struct SampleAvg {
+    sum_values: f64,
+    num_samples: u64,
+}
+
+impl SampleAvg {
+    pub fn avg(&self) -> f64 {
+        self.sum_values / self.num_samples as f64
+    }
+}
+
 pub struct OutputSingle {
     enabled: bool,
     warmup: u64,
     one_time: std::collections::BTreeMap<String, f64>,
     time_avg: std::collections::BTreeMap<String, TimeAvg>,
+    sample_avg: std::collections::BTreeMap<String, SampleAvg>,
 }
 
 impl OutputSingle {
@@ -36,6 +49,7 @@ impl OutputSingle {
             warmup: 0,
             one_time: std::collections::BTreeMap::new(),
             time_avg: std::collections::BTreeMap::new(),
+            sample_avg: std::collections::BTreeMap::new(),
         }
     }
 
@@ -59,8 +73,34 @@ impl OutputSingle {
         entry.last_value = value;
     }
 
+    /// Add a sample to a metric that measures the average of the samples.
+    /// Parameters:
+    /// - `name`: the metric name.
+    /// - `value`: the value of the sample, if collection is enabled.
+    pub fn sample_avg(&mut self, name: &str, value: f64) {
+        let entry = self.sample_avg.entry(name.to_string()).or_default();
+        if self.enabled {
+            entry.sum_values += value;
+            entry.num_samples += 1;
+        }
+    }
+
+    /// Add a sample to a metric that measures the fraction of positive samples.
+    /// Parameters:
+    /// - `name`: the metric name.
+    /// - `positive`: true if the sample is positive, if collection is enabled.
+    pub fn ratio(&mut self, name: &str, positive: bool) {
+        self.sample_avg(name, if positive { 1.0 } else { 0.0 });
+    }
+
+    /// Make sure that a sample average metric is saved, even if no sample
+    /// is added.
+    pub fn sample_avg_register(&mut self, name: &str) {
+        self.sample_avg.entry(name.to_string()).or_default();
+    }
+
     pub fn header(&self) -> String {
-        format!(
+        let mut ret = format!(
             "{},{}",
             self.one_time
                 .keys()
@@ -72,10 +112,14 @@ impl OutputSingle {
                 .cloned()
                 .collect::<Vec<String>>()
                 .join(",")
-        )
+        );
+        for name in self.sample_avg.keys() {
+            ret.push_str(&format!(",{}", name));
+        }
+        ret
     }
     pub fn to_csv(&self) -> String {
-        format!(
+        let mut ret = format!(
             "{},{}",
             self.one_time
                 .values()
@@ -87,7 +131,11 @@ impl OutputSingle {
                 .map(|x| x.avg().to_string())
                 .collect::<Vec<String>>()
                 .join(",")
-        )
+        );
+        for sample_avg in self.sample_avg.values() {
+            ret.push_str(&format!(",{}", sample_avg.avg()));
+        }
+        ret
     }
 
     pub fn enable(&mut self, now: u64) {
@@ -184,6 +232,8 @@ impl OutputSeries {
 pub struct Output {
     pub single: OutputSingle,
     pub series: OutputSeries,
+    /// The records of the jobs completed, if saved.
+    pub jobs: Vec<String>,
     pub config_csv: String,
 }
 
@@ -219,4 +269,27 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_sample_avg() -> anyhow::Result<()> {
+        let mut single = OutputSingle::new();
+        single.ratio("metric", true);
+        single.sample_avg("samples", 100.0);
+        single.sample_avg_register("other");
+        single.enable(0);
+        single.ratio("metric", true);
+        single.ratio("metric", false);
+        single.ratio("metric", false);
+        single.ratio("metric", true);
+        single.sample_avg("samples", 1.0);
+        single.sample_avg("samples", 2.0);
+        single.one_time("value", 1.0);
+        single.time_avg("avg", 0, 2.0);
+        single.finish(10);
+
+        assert_eq!("value,avg,metric,other,samples", single.header());
+        assert_eq!("1,2,0.5,NaN,1.5", single.to_csv());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,724 @@
+// SPDX-FileCopyrightText: © 2024 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+use rand::seq::SliceRandom;
+use rand_distr::Distribution;
+use std::io::BufRead;
+
+/// Description of a quantum computer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantumComputer {
+    /// Name.
+    pub name: String,
+    /// Maximum number of qubits of the circuits that can be executed.
+    pub max_qubits: u16,
+    /// Speed factor: the duration of a quantum iteration on this computer is
+    /// that drawn from the traces divided by this value.
+    pub speed: f64,
+}
+
+impl QuantumComputer {
+    /// Create a quantum computer with unlimited number of qubits and nominal
+    /// speed, i.e., where iterations last as in the traces.
+    pub fn unlimited(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            max_qubits: u16::MAX,
+            speed: 1.0,
+        }
+    }
+
+    /// Return true if a circuit with the given number of qubits fits.
+    pub fn fits(&self, num_qubits: u16) -> bool {
+        num_qubits <= self.max_qubits
+    }
+
+    /// Return the time, in ns, needed to execute on this computer a quantum
+    /// task with a given nominal duration.
+    pub fn duration(&self, nominal: u64) -> u64 {
+        (nominal as f64 / self.speed).round() as u64
+    }
+}
+
+impl std::str::FromStr for QuantumComputer {
+    type Err = anyhow::Error;
+    /// Parse a quantum computer specified as `name:max_qubits:speed`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = s.split(':').collect::<Vec<&str>>();
+        anyhow::ensure!(
+            tokens.len() == 3,
+            "invalid quantum computer specification: {}",
+            s
+        );
+        anyhow::ensure!(!tokens[0].is_empty(), "empty quantum computer name: {}", s);
+        let max_qubits = tokens[1]
+            .parse::<u16>()
+            .map_err(|_| anyhow::anyhow!("invalid number of qubits: {}", s))?;
+        let speed = tokens[2]
+            .parse::<f64>()
+            .map_err(|_| anyhow::anyhow!("invalid speed: {}", s))?;
+        anyhow::ensure!(max_qubits > 0, "vanishing number of qubits: {}", s);
+        anyhow::ensure!(speed > 0.0, "non-positive speed: {}", s);
+        Ok(Self {
+            name: tokens[0].to_string(),
+            max_qubits,
+            speed,
+        })
+    }
+}
+
+/// Policy to select the quantum computer that executes a task among those
+/// that are available and have enough qubits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QpuSelectionPolicy {
+    /// The first quantum computer in the order of specification.
+    FirstFit,
+    /// The quantum computer with the smallest number of qubits.
+    BestFit,
+    /// The quantum computer with the highest speed.
+    Fastest,
+    /// The quantum computer with the fewest quantum tasks in execution or
+    /// waiting in its queue, if any.
+    LeastLoaded,
+    /// A quantum computer drawn uniformly at random.
+    Random,
+}
+
+impl std::str::FromStr for QpuSelectionPolicy {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first-fit" => Ok(Self::FirstFit),
+            "best-fit" => Ok(Self::BestFit),
+            "fastest" => Ok(Self::Fastest),
+            "least-loaded" => Ok(Self::LeastLoaded),
+            "random" => Ok(Self::Random),
+            _ => anyhow::bail!("cannot parse '{}' as QPU selection policy", s),
+        }
+    }
+}
+
+impl QpuSelectionPolicy {
+    /// Select a quantum computer.
+    /// Parameters:
+    /// - `candidates`: the indices of the candidate quantum computers
+    /// - `quantum_computers`: all the quantum computers
+    /// - `loads`: the number of quantum tasks in execution on or waiting
+    ///   for each quantum computer
+    /// - `rng`: the pseudo-random number generator
+    ///
+    /// Return the index of the quantum computer selected, or None if there
+    /// are no candidates. Ties are broken in favor of the first candidate.
+    pub fn select(
+        &self,
+        candidates: &[usize],
+        quantum_computers: &[QuantumComputer],
+        loads: &[usize],
+        rng: &mut rand::rngs::StdRng,
+    ) -> Option<usize> {
+        match self {
+            Self::FirstFit => candidates.first().copied(),
+            Self::BestFit => candidates
+                .iter()
+                .min_by_key(|x| quantum_computers[**x].max_qubits)
+                .copied(),
+            Self::Fastest => candidates.iter().copied().reduce(|best, x| {
+                if quantum_computers[x].speed > quantum_computers[best].speed {
+                    x
+                } else {
+                    best
+                }
+            }),
+            Self::LeastLoaded => candidates.iter().min_by_key(|x| loads[**x]).copied(),
+            Self::Random => candidates.choose(rng).copied(),
+        }
+    }
+}
+
+/// Organization of the queues of the quantum tasks waiting for execution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueueMode {
+    /// A single queue shared by all the quantum computers.
+    Central,
+    /// A queue per quantum computer, where tasks are routed when ready.
+    PerQpu,
+    /// A queue per quantum computer, like `PerQpu`, but a quantum computer
+    /// with an empty queue steals tasks from the queues of the others.
+    PerQpuStealing,
+}
+
+impl std::str::FromStr for QueueMode {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "central" => Ok(Self::Central),
+            "per-qpu" => Ok(Self::PerQpu),
+            "per-qpu-stealing" => Ok(Self::PerQpuStealing),
+            _ => anyhow::bail!("cannot parse '{}' as quantum queue mode", s),
+        }
+    }
+}
+
+/// Session that reserves a quantum computer for a job during its iterative
+/// phase, like in Qiskit Runtime.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SessionPolicy {
+    /// Time after which the quantum computer is released if the job has
+    /// not submitted a new quantum task, in ns, or None to keep it reserved
+    /// until the job has no more quantum iterations.
+    pub idle_timeout: Option<u64>,
+}
+
+impl std::str::FromStr for SessionPolicy {
+    type Err = anyhow::Error;
+    /// Parse a session policy from its idle timeout, in s, or `inf`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "inf" {
+            return Ok(Self { idle_timeout: None });
+        }
+        match s.parse::<f64>() {
+            Ok(seconds) if seconds > 0.0 => Ok(Self {
+                idle_timeout: Some((seconds * 1e9).round() as u64),
+            }),
+            _ => anyhow::bail!("invalid session idle timeout: {}", s),
+        }
+    }
+}
+
+/// Execution of the circuits of multiple quantum tasks, possibly from
+/// different jobs, in a single batch on a quantum computer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatchPolicy {
+    /// Maximum number of circuits in a batch.
+    pub max_size: usize,
+    /// Maximum time a quantum task waits for a batch to be filled, in ns.
+    pub max_wait: u64,
+    /// Fixed overhead of the execution of a batch, in ns.
+    pub overhead: u64,
+    /// Multiplier of the sum of the durations of the circuits in a batch.
+    pub scale: f64,
+}
+
+impl BatchPolicy {
+    /// Return the duration, in ns, of a batch.
+    /// Parameters:
+    /// - `durations`: the durations of the circuits in isolation, in ns
+    pub fn duration(&self, durations: &[u64]) -> u64 {
+        self.overhead + (durations.iter().sum::<u64>() as f64 * self.scale).round() as u64
+    }
+}
+
+impl std::str::FromStr for BatchPolicy {
+    type Err = anyhow::Error;
+    /// Parse a batch policy specified as `max_size:max_wait:overhead:scale`,
+    /// with times in s.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = s.split(':').collect::<Vec<&str>>();
+        anyhow::ensure!(tokens.len() == 4, "invalid batch specification: {}", s);
+        let max_size = tokens[0]
+            .parse::<usize>()
+            .map_err(|_| anyhow::anyhow!("invalid maximum batch size: {}", s))?;
+        let values = tokens[1..]
+            .iter()
+            .map(|x| x.parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| anyhow::anyhow!("invalid batch specification: {}", s))?;
+        anyhow::ensure!(max_size > 0, "vanishing maximum batch size: {}", s);
+        anyhow::ensure!(
+            values.iter().all(|x| *x >= 0.0),
+            "negative values in batch specification: {}",
+            s
+        );
+        Ok(Self {
+            max_size,
+            max_wait: (values[0] * 1e9).round() as u64,
+            overhead: (values[1] * 1e9).round() as u64,
+            scale: values[2],
+        })
+    }
+}
+
+/// Concurrent execution of the circuits of multiple quantum tasks on
+/// a quantum computer, as long as their total number of qubits fits.
+/// All the circuits executing on a quantum computer are slowed down by
+/// the same factor, which depends on how many they are, hence the remaining
+/// durations of those in execution are rescaled whenever a circuit starts
+/// or ends there. Instead, the fidelity penalty of a circuit is determined
+/// when it starts, based on the number of circuits already executing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MultiProgrammingPolicy {
+    /// Relative increase of the duration of a circuit for every other
+    /// circuit sharing the quantum computer.
+    pub slowdown: f64,
+    /// Relative decrease of the fidelity of a circuit for every other
+    /// circuit sharing the quantum computer.
+    pub fidelity_penalty: f64,
+}
+
+impl MultiProgrammingPolicy {
+    /// Return the duration, in ns, of a circuit.
+    /// Parameters:
+    /// - `duration`: the duration of the circuit in isolation, in ns
+    /// - `num_others`: the number of other circuits sharing the quantum computer
+    pub fn duration(&self, duration: u64, num_others: usize) -> u64 {
+        (duration as f64 * (1.0 + self.slowdown * num_others as f64)).round() as u64
+    }
+
+    /// Return the remaining duration, in ns, of a circuit in execution when
+    /// the number of other circuits sharing the quantum computer changes.
+    /// Parameters:
+    /// - `remaining`: the remaining duration with the previous number, in ns
+    /// - `num_others_before`: the previous number of other circuits
+    /// - `num_others_after`: the new number of other circuits
+    pub fn rescale(
+        &self,
+        remaining: u64,
+        num_others_before: usize,
+        num_others_after: usize,
+    ) -> u64 {
+        (remaining as f64 * (1.0 + self.slowdown * num_others_after as f64)
+            / (1.0 + self.slowdown * num_others_before as f64))
+            .round() as u64
+    }
+
+    /// Return the fidelity of a circuit, relative to that in isolation.
+    /// Parameters:
+    /// - `num_others`: the number of other circuits sharing the quantum computer
+    pub fn fidelity(&self, num_others: usize) -> f64 {
+        (1.0 - self.fidelity_penalty).powi(num_others as i32)
+    }
+}
+
+impl std::str::FromStr for MultiProgrammingPolicy {
+    type Err = anyhow::Error;
+    /// Parse a multi-programming policy specified as
+    /// `slowdown:fidelity_penalty`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (slowdown, fidelity_penalty) = s.split_once(':').ok_or(anyhow::anyhow!(
+            "invalid multi-programming specification: {}",
+            s
+        ))?;
+        let slowdown = slowdown
+            .parse::<f64>()
+            .map_err(|_| anyhow::anyhow!("invalid slowdown: {}", s))?;
+        let fidelity_penalty = fidelity_penalty
+            .parse::<f64>()
+            .map_err(|_| anyhow::anyhow!("invalid fidelity penalty: {}", s))?;
+        anyhow::ensure!(slowdown >= 0.0, "negative slowdown: {}", s);
+        anyhow::ensure!(
+            (0.0..=1.0).contains(&fidelity_penalty),
+            "fidelity penalty out of [0,1]: {}",
+            s
+        );
+        Ok(Self {
+            slowdown,
+            fidelity_penalty,
+        })
+    }
+}
+
+/// What happens to the quantum tasks executing on a quantum computer when
+/// a downtime begins.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DowntimePolicy {
+    /// The tasks are completed, but no new tasks are started.
+    Finish,
+    /// The tasks are interrupted and their jobs are dropped.
+    Abort,
+    /// The tasks are interrupted and scheduled again from scratch.
+    Requeue,
+}
+
+impl std::str::FromStr for DowntimePolicy {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "finish" => Ok(Self::Finish),
+            "abort" => Ok(Self::Abort),
+            "requeue" => Ok(Self::Requeue),
+            _ => anyhow::bail!("cannot parse '{}' as downtime policy", s),
+        }
+    }
+}
+
+/// Period of time during which a quantum computer cannot start tasks,
+/// e.g., because of calibration or maintenance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Downtime {
+    /// Beginning of the downtime, in ns.
+    pub start: u64,
+    /// End of the downtime, in ns.
+    pub end: u64,
+}
+
+/// Random failures of quantum computers, with exponentially distributed
+/// times between failures and times to repair.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FailureModel {
+    /// Mean time between failures, in s.
+    pub mtbf: f64,
+    /// Mean time to repair, in s.
+    pub mttr: f64,
+}
+
+impl FailureModel {
+    /// Draw the time, in ns, until the next failure of a quantum computer.
+    pub fn time_to_failure(&self, rng: &mut rand::rngs::StdRng) -> u64 {
+        Self::draw(self.mtbf, rng)
+    }
+
+    /// Draw the time, in ns, needed to repair a quantum computer.
+    pub fn time_to_repair(&self, rng: &mut rand::rngs::StdRng) -> u64 {
+        Self::draw(self.mttr, rng)
+    }
+
+    fn draw(mean: f64, rng: &mut rand::rngs::StdRng) -> u64 {
+        (rand_distr::Exp::new(1.0 / mean).unwrap().sample(rng) * 1e9).round() as u64
+    }
+}
+
+impl std::str::FromStr for FailureModel {
+    type Err = anyhow::Error;
+    /// Parse a failure model specified as `mtbf:mttr`, in s.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mtbf, mttr) = s
+            .split_once(':')
+            .ok_or(anyhow::anyhow!("invalid failure specification: {}", s))?;
+        let mtbf = mtbf
+            .parse::<f64>()
+            .map_err(|_| anyhow::anyhow!("invalid MTBF: {}", s))?;
+        let mttr = mttr
+            .parse::<f64>()
+            .map_err(|_| anyhow::anyhow!("invalid MTTR: {}", s))?;
+        anyhow::ensure!(mtbf > 0.0 && mttr > 0.0, "non-positive MTBF or MTTR: {}", s);
+        Ok(Self { mtbf, mttr })
+    }
+}
+
+/// Parse the downtimes of a fleet of quantum computers, which can be
+/// specified either as `period:duration`, in s, so that every quantum computer
+/// goes offline for `duration` at the end of every `period`, or as the path
+/// of a CSV file where each line is `qpu_id,start,duration`, in s.
+/// The periodic downtimes of the quantum computers are staggered evenly over
+/// the period, i.e., the `i`-th of `n` quantum computers goes offline for
+/// the first time after `period * (1 + i / n)`, so that they are not all
+/// offline at the same time.
+/// Parameters:
+/// - `s`: the specification
+/// - `num_quantum_computers`: the number of quantum computers
+/// - `horizon`: the time, in ns, after which downtimes can be ignored
+///
+/// Return the downtimes of each quantum computer, sorted by start time.
+pub fn parse_downtimes(
+    s: &str,
+    num_quantum_computers: usize,
+    horizon: u64,
+) -> anyhow::Result<Vec<Vec<Downtime>>> {
+    let mut ret = vec![vec![]; num_quantum_computers];
+    let periodic = s.split_once(':').and_then(|(period, duration)| {
+        Some((period.parse::<f64>().ok()?, duration.parse::<f64>().ok()?))
+    });
+    if let Some((period, duration)) = periodic {
+        anyhow::ensure!(
+            duration > 0.0 && duration < period,
+            "invalid calibration period/duration: {}",
+            s
+        );
+        let period = (period * 1e9).round() as u64;
+        let duration = (duration * 1e9).round() as u64;
+        for (qpu_id, downtimes) in ret.iter_mut().enumerate() {
+            let mut start = period + period * qpu_id as u64 / num_quantum_computers as u64;
+            while start < horizon {
+                downtimes.push(Downtime {
+                    start,
+                    end: start + duration,
+                });
+                start += period;
+            }
+        }
+        return Ok(ret);
+    }
+
+    let file = std::fs::File::open(s)
+        .map_err(|err| anyhow::anyhow!("cannot open downtime file '{}': {}", s, err))?;
+    let reader = std::io::BufReader::new(file);
+    for line in reader.lines().map_while(Result::ok) {
+        let tokens = line.split(',').collect::<Vec<&str>>();
+        anyhow::ensure!(
+            tokens.len() == 3,
+            "invalid line from file '{}': {}",
+            s,
+            line
+        );
+        if let (Ok(qpu_id), Ok(start), Ok(duration)) = (
+            tokens[0].parse::<usize>(),
+            tokens[1].parse::<f64>(),
+            tokens[2].parse::<f64>(),
+        ) {
+            anyhow::ensure!(
+                qpu_id < num_quantum_computers,
+                "invalid quantum computer in file '{}': {}",
+                s,
+                line
+            );
+            anyhow::ensure!(
+                start >= 0.0 && duration > 0.0,
+                "invalid downtime in file '{}': {}",
+                s,
+                line
+            );
+            let start = (start * 1e9).round() as u64;
+            ret[qpu_id].push(Downtime {
+                start,
+                end: start + (duration * 1e9).round() as u64,
+            });
+        }
+    }
+    for downtimes in &mut ret {
+        downtimes.sort_by_key(|x| x.start);
+        anyhow::ensure!(
+            downtimes.windows(2).all(|x| x[0].end <= x[1].start),
+            "overlapping downtimes in file '{}'",
+            s
+        );
+    }
+    Ok(ret)
+}
+
+/// Parse a fleet of quantum computers from a list of specifications
+/// separated by semicolons, where each specification is either
+/// `name:max_qubits:speed` or `N*name:max_qubits:speed` to add `N`
+/// identical quantum computers.
+pub fn parse_quantum_computers(s: &str) -> anyhow::Result<Vec<QuantumComputer>> {
+    let mut ret = vec![];
+    for token in s.split(';') {
+        let (multiplicity, spec) = match token.split_once('*') {
+            Some((multiplicity, spec)) => (
+                multiplicity
+                    .parse::<usize>()
+                    .map_err(|_| anyhow::anyhow!("invalid multiplicity: {}", token))?,
+                spec,
+            ),
+            None => (1, token),
+        };
+        let quantum_computer = spec.parse::<QuantumComputer>()?;
+        for _ in 0..multiplicity {
+            ret.push(quantum_computer.clone());
+        }
+    }
+    anyhow::ensure!(!ret.is_empty(), "no quantum computers specified");
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quantum_computers() -> anyhow::Result<()> {
+        let fleet = parse_quantum_computers("2*falcon:27:1;heron:133:2.5")?;
+        assert_eq!(3, fleet.len());
+        assert_eq!(fleet[0], fleet[1]);
+        assert_eq!("falcon", fleet[0].name);
+        assert_eq!(27, fleet[0].max_qubits);
+        assert_eq!("heron", fleet[2].name);
+        assert_eq!(133, fleet[2].max_qubits);
+        assert_eq!(400, fleet[2].duration(1000));
+        assert!(fleet[0].fits(27));
+        assert!(!fleet[0].fits(28));
+
+        for invalid in [
+            "",
+            "falcon",
+            "falcon:27",
+            ":27:1",
+            "falcon:0:1",
+            "falcon:27:0",
+            "falcon:27:1:1",
+            "x*falcon:27:1",
+            "0*falcon:27:1",
+        ] {
+            assert!(parse_quantum_computers(invalid).is_err(), "{}", invalid);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_qpu_selection_policy() -> anyhow::Result<()> {
+        use rand::SeedableRng;
+        use std::str::FromStr;
+
+        let fleet = parse_quantum_computers("a:27:1;b:133:2;c:16:1;d:27:2;e:16:0.5")?;
+        let loads = vec![5, 1, 0, 1, 2];
+        let candidates = vec![0, 1, 3, 4];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+
+        for (policy, expected) in [
+            ("first-fit", 0),
+            ("best-fit", 4),
+            ("fastest", 1),
+            ("least-loaded", 1),
+        ] {
+            assert_eq!(
+                Some(expected),
+                QpuSelectionPolicy::from_str(policy)?.select(&candidates, &fleet, &loads, &mut rng),
+                "{}",
+                policy
+            );
+        }
+        for _ in 0..10 {
+            let selected = QpuSelectionPolicy::Random
+                .select(&candidates, &fleet, &loads, &mut rng)
+                .unwrap();
+            assert!(candidates.contains(&selected));
+        }
+        assert!(QpuSelectionPolicy::FirstFit
+            .select(&[], &fleet, &loads, &mut rng)
+            .is_none());
+        assert!(QpuSelectionPolicy::from_str("worst-fit").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_policy() -> anyhow::Result<()> {
+        use std::str::FromStr;
+
+        let batch_policy = BatchPolicy::from_str("4:10:0.5:0.8")?;
+        assert_eq!(4, batch_policy.max_size);
+        assert_eq!(10_000_000_000, batch_policy.max_wait);
+        assert_eq!(500_000_000, batch_policy.overhead);
+        assert_eq!(
+            2_900_000_000,
+            batch_policy.duration(&[1_000_000_000, 2_000_000_000])
+        );
+        for invalid in ["", "4:10:0.5", "0:10:0.5:0.8", "4:-1:0.5:0.8", "4:10:x:0.8"] {
+            assert!(BatchPolicy::from_str(invalid).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_programming_policy() -> anyhow::Result<()> {
+        use std::str::FromStr;
+
+        let policy = MultiProgrammingPolicy::from_str("0.25:0.1")?;
+        assert_eq!(1_000_000_000, policy.duration(1_000_000_000, 0));
+        assert_eq!(1_500_000_000, policy.duration(1_000_000_000, 2));
+        assert_eq!(1_000_000_000, policy.rescale(1_500_000_000, 2, 0));
+        assert_eq!(1_250_000_000, policy.rescale(1_000_000_000, 0, 1));
+        assert_eq!(1.0, policy.fidelity(0));
+        assert!((policy.fidelity(2) - 0.81).abs() < 1e-9);
+        for invalid in ["", "0.25", "-1:0.1", "0.25:1.5", "x:0.1"] {
+            assert!(MultiProgrammingPolicy::from_str(invalid).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_downtimes() -> anyhow::Result<()> {
+        let downtimes = parse_downtimes("10:2", 2, 35_000_000_000)?;
+        assert_eq!(2, downtimes.len());
+        assert_eq!(
+            vec![
+                Downtime {
+                    start: 10_000_000_000,
+                    end: 12_000_000_000
+                },
+                Downtime {
+                    start: 20_000_000_000,
+                    end: 22_000_000_000
+                },
+                Downtime {
+                    start: 30_000_000_000,
+                    end: 32_000_000_000
+                }
+            ],
+            downtimes[0]
+        );
+        assert_eq!(
+            vec![
+                Downtime {
+                    start: 15_000_000_000,
+                    end: 17_000_000_000
+                },
+                Downtime {
+                    start: 25_000_000_000,
+                    end: 27_000_000_000
+                }
+            ],
+            downtimes[1]
+        );
+
+        let filename =
+            std::env::temp_dir().join(format!("test_parse_downtimes_{}.csv", std::process::id()));
+        std::fs::write(&filename, "qpu_id,start,duration\n1,20,5\n1,5,1.5\n")?;
+        let downtimes = parse_downtimes(filename.to_str().unwrap(), 2, 0)?;
+        assert!(downtimes[0].is_empty());
+        assert_eq!(
+            vec![
+                Downtime {
+                    start: 5_000_000_000,
+                    end: 6_500_000_000
+                },
+                Downtime {
+                    start: 20_000_000_000,
+                    end: 25_000_000_000
+                }
+            ],
+            downtimes[1]
+        );
+        assert!(parse_downtimes(filename.to_str().unwrap(), 1, 0).is_err());
+        std::fs::remove_file(&filename)?;
+
+        for invalid in ["10:10", "10:0", "nonexistent.csv"] {
+            assert!(parse_downtimes(invalid, 1, 0).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_failure_model() -> anyhow::Result<()> {
+        use rand::SeedableRng;
+        use std::str::FromStr;
+
+        let failure_model = FailureModel::from_str("1000:10")?;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let num_samples = 10000;
+        let avg_failure = (0..num_samples)
+            .map(|_| failure_model.time_to_failure(&mut rng) as f64)
+            .sum::<f64>()
+            / num_samples as f64;
+        let avg_repair = (0..num_samples)
+            .map(|_| failure_model.time_to_repair(&mut rng) as f64)
+            .sum::<f64>()
+            / num_samples as f64;
+        assert!((avg_failure / 1e12 - 1.0).abs() < 0.05, "{}", avg_failure);
+        assert!((avg_repair / 1e10 - 1.0).abs() < 0.05, "{}", avg_repair);
+        for invalid in ["", "1000", "0:10", "1000:-1", "x:10"] {
+            assert!(FailureModel::from_str(invalid).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_session_policy() -> anyhow::Result<()> {
+        use std::str::FromStr;
+
+        assert_eq!(None, SessionPolicy::from_str("inf")?.idle_timeout);
+        assert_eq!(
+            Some(2_500_000_000),
+            SessionPolicy::from_str("2.5")?.idle_timeout
+        );
+        for invalid in ["", "0", "-1", "never"] {
+            assert!(SessionPolicy::from_str(invalid).is_err());
+        }
+
+        Ok(())
+    }
+}
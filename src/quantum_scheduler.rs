@@ -0,0 +1,395 @@
+// SPDX-FileCopyrightText: © 2024 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use weighted_rand::builder::NewBuilder;
+
+/// Policy to select which of the pending quantum tasks is dispatched next.
+pub trait QuantumScheduler: Send {
+    /// Select the next quantum task to be dispatched.
+    /// Parameters:
+    /// - `pending`: the pending quantum tasks, never empty
+    /// - `active_jobs`: the active jobs, indexed by their identifiers
+    /// - `now`: the current time, in ns
+    ///
+    /// Return the index in `pending` of the task selected, or None if
+    /// no task should be dispatched now.
+    fn select(
+        &mut self,
+        pending: &[crate::task::Task],
+        active_jobs: &std::collections::HashMap<u64, crate::job::Job>,
+        now: u64,
+    ) -> Option<usize>;
+}
+
+/// Select the task that has been pending for the longest time.
+pub struct Fifo {}
+
+impl QuantumScheduler for Fifo {
+    fn select(
+        &mut self,
+        _pending: &[crate::task::Task],
+        _active_jobs: &std::collections::HashMap<u64, crate::job::Job>,
+        _now: u64,
+    ) -> Option<usize> {
+        Some(0)
+    }
+}
+
+/// Select the task that has been pending for the shortest time.
+pub struct Lifo {}
+
+impl QuantumScheduler for Lifo {
+    fn select(
+        &mut self,
+        pending: &[crate::task::Task],
+        _active_jobs: &std::collections::HashMap<u64, crate::job::Job>,
+        _now: u64,
+    ) -> Option<usize> {
+        Some(pending.len() - 1)
+    }
+}
+
+/// Select a task uniformly at random.
+pub struct Random {
+    rng: rand::rngs::StdRng,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: rand::rngs::StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl QuantumScheduler for Random {
+    fn select(
+        &mut self,
+        pending: &[crate::task::Task],
+        _active_jobs: &std::collections::HashMap<u64, crate::job::Job>,
+        _now: u64,
+    ) -> Option<usize> {
+        let indices: Vec<usize> = (0..pending.len()).collect();
+        indices.choose(&mut self.rng).copied()
+    }
+}
+
+/// Select a task at random with a probability proportional to the
+/// priority of its job.
+pub struct Weighted {}
+
+impl QuantumScheduler for Weighted {
+    fn select(
+        &mut self,
+        pending: &[crate::task::Task],
+        active_jobs: &std::collections::HashMap<u64, crate::job::Job>,
+        _now: u64,
+    ) -> Option<usize> {
+        let mut weights = vec![];
+        for task in pending {
+            weights.push(active_jobs.get(&task.job_id).unwrap().priority as u32);
+        }
+        Some(
+            weighted_rand::builder::WalkerTableBuilder::new(&weights)
+                .build()
+                .next(),
+        )
+    }
+}
+
+/// Select deterministically the task with the highest priority, breaking
+/// ties in favor of the task that has been pending for the longest time.
+pub struct StrictPriority {}
+
+impl QuantumScheduler for StrictPriority {
+    fn select(
+        &mut self,
+        pending: &[crate::task::Task],
+        active_jobs: &std::collections::HashMap<u64, crate::job::Job>,
+        _now: u64,
+    ) -> Option<usize> {
+        select_highest(pending, |task| {
+            active_jobs.get(&task.job_id).unwrap().priority as f64
+        })
+    }
+}
+
+/// Select deterministically the task with the highest effective priority,
+/// which is equal to the priority of its job plus a term that grows linearly
+/// with the time the task has been waiting.
+/// Ties are broken in favor of the task that has been pending for the
+/// longest time.
+pub struct Aging {
+    /// Increase of the effective priority per second of waiting.
+    rate: f64,
+}
+
+impl Aging {
+    pub fn new(rate: f64) -> Self {
+        Self { rate }
+    }
+}
+
+impl QuantumScheduler for Aging {
+    fn select(
+        &mut self,
+        pending: &[crate::task::Task],
+        active_jobs: &std::collections::HashMap<u64, crate::job::Job>,
+        now: u64,
+    ) -> Option<usize> {
+        select_highest(pending, |task| {
+            active_jobs.get(&task.job_id).unwrap().priority as f64
+                + self.rate * crate::simulation::to_seconds(now - task.start_time)
+        })
+    }
+}
+
+/// Select deterministically the task whose job has the smallest expected
+/// remaining quantum execution time, i.e., shortest remaining processing
+/// time first.
+/// Ties are broken in favor of the task that has been pending for the
+/// longest time.
+pub struct ShortestRemaining {}
+
+impl QuantumScheduler for ShortestRemaining {
+    fn select(
+        &mut self,
+        pending: &[crate::task::Task],
+        active_jobs: &std::collections::HashMap<u64, crate::job::Job>,
+        _now: u64,
+    ) -> Option<usize> {
+        select_highest(pending, |task| {
+            -(active_jobs
+                .get(&task.job_id)
+                .unwrap()
+                .remaining_quantum_time() as f64)
+        })
+    }
+}
+
+/// Select deterministically the task whose job has received the smallest
+/// quantum execution time so far, i.e., least attained service.
+/// Ties are broken in favor of the task that has been pending for the
+/// longest time.
+pub struct LeastAttained {}
+
+impl QuantumScheduler for LeastAttained {
+    fn select(
+        &mut self,
+        pending: &[crate::task::Task],
+        active_jobs: &std::collections::HashMap<u64, crate::job::Job>,
+        _now: u64,
+    ) -> Option<usize> {
+        select_highest(pending, |task| {
+            -(active_jobs
+                .get(&task.job_id)
+                .unwrap()
+                .attained_quantum_time() as f64)
+        })
+    }
+}
+
+/// Select deterministically the task whose job has the earliest deadline,
+/// with jobs without a deadline served last.
+/// Ties are broken in favor of the task that has been pending for the
+/// longest time.
+pub struct EarliestDeadline {}
+
+impl QuantumScheduler for EarliestDeadline {
+    fn select(
+        &mut self,
+        pending: &[crate::task::Task],
+        active_jobs: &std::collections::HashMap<u64, crate::job::Job>,
+        _now: u64,
+    ) -> Option<usize> {
+        select_highest(pending, |task| {
+            match active_jobs.get(&task.job_id).unwrap().deadline {
+                Some(deadline) => -(deadline as f64),
+                None => f64::NEG_INFINITY,
+            }
+        })
+    }
+}
+
+/// Return the index of the task with the highest value of `key`, with ties
+/// broken in favor of the earliest start time and then the lowest job ID.
+fn select_highest<F>(pending: &[crate::task::Task], key: F) -> Option<usize>
+where
+    F: Fn(&crate::task::Task) -> f64,
+{
+    let mut best: Option<(usize, f64)> = None;
+    for (index, task) in pending.iter().enumerate() {
+        let value = key(task);
+        let better = match best {
+            None => true,
+            Some((best_index, best_value)) => {
+                let best_task = &pending[best_index];
+                value > best_value
+                    || (value == best_value
+                        && (task.start_time, task.job_id)
+                            < (best_task.start_time, best_task.job_id))
+            }
+        };
+        if better {
+            best = Some((index, value));
+        }
+    }
+    best.map(|(index, _)| index)
+}
+
+/// Create a quantum scheduler from its name.
+/// Parameters:
+/// - `policy`: the name of the policy, optionally followed by its
+///   parameters separated by semicolons, e.g., `aging;0.1`
+/// - `seed`: seed to initialize the pseudo-random number generator, if needed
+pub fn make_quantum_scheduler(
+    policy: &str,
+    seed: u64,
+) -> anyhow::Result<Box<dyn QuantumScheduler>> {
+    if let Some(rate) = policy.strip_prefix("aging;") {
+        let rate = rate
+            .parse::<f64>()
+            .map_err(|_| anyhow::anyhow!("invalid aging rate: {}", rate))?;
+        anyhow::ensure!(rate >= 0.0, "negative aging rate: {}", rate);
+        return Ok(Box::new(Aging::new(rate)));
+    }
+    match policy {
+        "fifo" => Ok(Box::new(Fifo {})),
+        "lifo" => Ok(Box::new(Lifo {})),
+        "random" => Ok(Box::new(Random::new(seed))),
+        "weighted" => Ok(Box::new(Weighted {})),
+        "priority" => Ok(Box::new(StrictPriority {})),
+        "srpt" => Ok(Box::new(ShortestRemaining {})),
+        "las" => Ok(Box::new(LeastAttained {})),
+        "edf" => Ok(Box::new(EarliestDeadline {})),
+        _ => anyhow::bail!("cannot parse '{}' as quantum schedule policy", policy),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_task(job_id: u64, start_time: u64) -> crate::task::Task {
+        crate::task::Task {
+            job_id,
+            task_type: crate::task::TaskType::Quantum(1000),
+            start_time,
+            last_update: start_time,
+            resource: None,
+            branch: 0,
+        }
+    }
+
+    #[test]
+    fn test_make_quantum_scheduler() -> anyhow::Result<()> {
+        let pending = vec![make_task(0, 0), make_task(1, 10), make_task(2, 20)];
+        let active_jobs = std::collections::HashMap::new();
+
+        let mut fifo = make_quantum_scheduler("fifo", 42)?;
+        assert_eq!(Some(0), fifo.select(&pending, &active_jobs, 100));
+
+        let mut lifo = make_quantum_scheduler("lifo", 42)?;
+        assert_eq!(Some(2), lifo.select(&pending, &active_jobs, 100));
+
+        let mut random = make_quantum_scheduler("random", 42)?;
+        for _ in 0..10 {
+            assert!(random.select(&pending, &active_jobs, 100).unwrap() < pending.len());
+        }
+
+        assert!(make_quantum_scheduler("unknown", 42).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_priority_schedulers() -> anyhow::Result<()> {
+        let mut active_jobs = std::collections::HashMap::new();
+        for (job_id, priority) in [(0, 1), (1, 4), (2, 4), (3, 2)] {
+            active_jobs.insert(job_id, crate::job::Job::for_testing(job_id, 4, priority));
+        }
+        let second = 1_000_000_000;
+        let pending = vec![
+            make_task(0, 0),
+            make_task(2, 20 * second),
+            make_task(1, 10 * second),
+            make_task(3, 30 * second),
+        ];
+        let now = 100 * second;
+
+        // same priority: the task waiting for longer wins
+        let mut priority = make_quantum_scheduler("priority", 42)?;
+        assert_eq!(Some(2), priority.select(&pending, &active_jobs, now));
+
+        // no aging: same as strict priority
+        let mut aging = make_quantum_scheduler("aging;0", 42)?;
+        assert_eq!(Some(2), aging.select(&pending, &active_jobs, now));
+
+        // effective priorities: 1 + 10 = 11, 4 + 8 = 12, 4 + 9 = 13, 2 + 7 = 9
+        let mut aging = make_quantum_scheduler("aging;0.1", 42)?;
+        assert_eq!(Some(2), aging.select(&pending, &active_jobs, now));
+
+        // effective priorities: 1 + 100 = 101, 4 + 80 = 84, 4 + 90 = 94, 2 + 70 = 72
+        let mut aging = make_quantum_scheduler("aging;1", 42)?;
+        assert_eq!(Some(0), aging.select(&pending, &active_jobs, now));
+
+        assert!(make_quantum_scheduler("aging", 42).is_err());
+        assert!(make_quantum_scheduler("aging;-1", 42).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_size_based_schedulers() -> anyhow::Result<()> {
+        let mut active_jobs = std::collections::HashMap::new();
+        for job_id in 0..3 {
+            active_jobs.insert(job_id, crate::job::Job::for_testing(job_id, 4, 1));
+        }
+        let second = 1_000_000_000;
+        let job = active_jobs.get_mut(&0).unwrap();
+        for _ in 0..3 {
+            job.quantum_task_completed(second);
+        }
+        active_jobs
+            .get_mut(&1)
+            .unwrap()
+            .quantum_task_completed(5 * second);
+        let pending = vec![make_task(1, 0), make_task(0, 10), make_task(2, 20)];
+
+        // remaining: 9 s, 7 s, 10 s
+        let mut srpt = make_quantum_scheduler("srpt", 42)?;
+        assert_eq!(Some(1), srpt.select(&pending, &active_jobs, 100));
+
+        // attained: 5 s, 3 s, 0 s
+        let mut las = make_quantum_scheduler("las", 42)?;
+        assert_eq!(Some(2), las.select(&pending, &active_jobs, 100));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_edf_scheduler() -> anyhow::Result<()> {
+        let mut active_jobs = std::collections::HashMap::new();
+        for (job_id, deadline) in [(0, None), (1, Some(500)), (2, Some(300)), (3, Some(300))] {
+            let mut job = crate::job::Job::for_testing(job_id, 4, 1);
+            job.deadline = deadline;
+            active_jobs.insert(job_id, job);
+        }
+        let mut edf = make_quantum_scheduler("edf", 42)?;
+
+        let pending = vec![
+            make_task(0, 0),
+            make_task(3, 20),
+            make_task(1, 5),
+            make_task(2, 10),
+        ];
+        assert_eq!(Some(3), edf.select(&pending, &active_jobs, 100));
+
+        let pending = vec![make_task(0, 0), make_task(0, 5)];
+        assert_eq!(Some(0), edf.select(&pending, &active_jobs, 100));
+
+        Ok(())
+    }
+}
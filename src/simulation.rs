@@ -5,7 +5,6 @@ use rand::seq::SliceRandom;
 use rand::SeedableRng;
 use rand_distr::Distribution;
 use std::str::FromStr;
-use weighted_rand::builder::NewBuilder;
 
 static GIGA: u64 = 1000000000;
 
@@ -17,6 +16,45 @@ fn to_nanoseconds(s: f64) -> u64 {
     (s * GIGA as f64).round() as u64
 }
 
+/// Return the name of the metric with the ratio of jobs with a given label
+/// that missed their deadline.
+fn deadline_miss_metric(label: &str) -> String {
+    format!("deadline_miss_ratio_{}", label.replace(',', "_"))
+}
+
+/// Return the name of the metric with the ratio of classical tasks of jobs
+/// with a given label that suffered from a cold start.
+fn cold_start_metric(label: &str) -> String {
+    format!("cold_start_ratio_{}", label.replace(',', "_"))
+}
+
+/// Return the name of the metric with the utilization of a quantum computer.
+fn qpu_utilization_metric(qpu_id: usize) -> String {
+    format!("qc_utilization_{}", qpu_id)
+}
+
+/// Return the name of the metric with the availability of a quantum computer.
+fn qpu_availability_metric(qpu_id: usize) -> String {
+    format!("qc_availability_{}", qpu_id)
+}
+
+/// Return the name of the metric with the queue length of a quantum computer.
+fn qpu_queue_len_metric(qpu_id: usize) -> String {
+    format!("qc_queue_len_{}", qpu_id)
+}
+
+/// Reservation of a quantum computer for a job.
+#[derive(Debug, Clone, Copy)]
+struct Session {
+    /// Identifier of the job holding the quantum computer.
+    job_id: u64,
+    /// Unique identifier of the session.
+    session_id: u64,
+    /// Last time when a quantum task of the job completed on the quantum
+    /// computer, from which the idle timeout counts.
+    idle_since: u64,
+}
+
 /// For all the events there is the time when it is scheduled to occur.
 #[derive(PartialEq, Eq)]
 enum Event {
@@ -32,6 +70,31 @@ enum Event {
     QuantumIterationEnd(u64),
     /// Update classical tasks.
     UpdateClassicalTasks(u64),
+    /// The serverless function instance of a job is ready to execute its
+    /// classical task after a cold start, with the job identifier.
+    ColdStartEnd(u64, u64),
+    /// A task of a job reaches the stage that executes it, after
+    /// a hand-off between a classical and a quantum stage, with the job
+    /// identifier and the index of the task within its stage.
+    HandOffEnd(u64, u64, usize),
+    /// The autoscaler decides the number of serverless workers.
+    AutoscalerTick(u64),
+    /// New serverless workers are ready, with their number.
+    WorkersReady(u64, usize),
+    /// The idle timeout of a session expires, with the quantum computer
+    /// identifier and the session identifier.
+    SessionTimeout(u64, usize, u64),
+    /// The maximum waiting time of a pending quantum task for a batch
+    /// to be filled expires.
+    BatchTimeout(u64),
+    /// A quantum computer goes offline, with its identifier.
+    DowntimeStart(u64, usize),
+    /// A quantum computer comes back online, with its identifier.
+    DowntimeEnd(u64, usize),
+    /// A quantum computer fails, with its identifier.
+    QpuFailure(u64, usize),
+    /// A quantum computer is repaired, with its identifier.
+    QpuRepair(u64, usize),
 }
 
 impl Event {
@@ -42,7 +105,41 @@ impl Event {
             | Self::ExperimentEnd(t)
             | Self::Progress(t, _)
             | Self::QuantumIterationEnd(t)
-            | Self::UpdateClassicalTasks(t) => *t,
+            | Self::UpdateClassicalTasks(t)
+            | Self::ColdStartEnd(t, _)
+            | Self::HandOffEnd(t, _, _)
+            | Self::AutoscalerTick(t)
+            | Self::WorkersReady(t, _)
+            | Self::SessionTimeout(t, _, _)
+            | Self::BatchTimeout(t)
+            | Self::DowntimeStart(t, _)
+            | Self::DowntimeEnd(t, _)
+            | Self::QpuFailure(t, _)
+            | Self::QpuRepair(t, _) => *t,
+        }
+    }
+
+    /// Return the name of the event and its parameters other than the time.
+    fn describe(&self) -> (&'static str, Vec<u64>) {
+        match self {
+            Self::JobStart(_) => ("JobStart", vec![]),
+            Self::WarmupPeriodEnd(_) => ("WarmupPeriodEnd", vec![]),
+            Self::ExperimentEnd(_) => ("ExperimentEnd", vec![]),
+            Self::Progress(_, percentage) => ("Progress", vec![*percentage as u64]),
+            Self::QuantumIterationEnd(_) => ("QuantumIterationEnd", vec![]),
+            Self::UpdateClassicalTasks(_) => ("UpdateClassicalTasks", vec![]),
+            Self::ColdStartEnd(_, job_id) => ("ColdStartEnd", vec![*job_id]),
+            Self::HandOffEnd(_, job_id, branch) => ("HandOffEnd", vec![*job_id, *branch as u64]),
+            Self::AutoscalerTick(_) => ("AutoscalerTick", vec![]),
+            Self::WorkersReady(_, num_workers) => ("WorkersReady", vec![*num_workers as u64]),
+            Self::SessionTimeout(_, qpu_id, session_id) => {
+                ("SessionTimeout", vec![*qpu_id as u64, *session_id])
+            }
+            Self::BatchTimeout(_) => ("BatchTimeout", vec![]),
+            Self::DowntimeStart(_, qpu_id) => ("DowntimeStart", vec![*qpu_id as u64]),
+            Self::DowntimeEnd(_, qpu_id) => ("DowntimeEnd", vec![*qpu_id as u64]),
+            Self::QpuFailure(_, qpu_id) => ("QpuFailure", vec![*qpu_id as u64]),
+            Self::QpuRepair(_, qpu_id) => ("QpuRepair", vec![*qpu_id as u64]),
         }
     }
 }
@@ -64,6 +161,7 @@ impl Ord for Event {
 pub struct EventQueue {
     queue: std::collections::BinaryHeap<Event>,
     update_classical_task_times: std::collections::BTreeSet<u64>,
+    batch_timeout_times: std::collections::BTreeSet<u64>,
 }
 
 impl EventQueue {
@@ -75,6 +173,11 @@ impl EventQueue {
                 self.update_classical_task_times.insert(*t);
             }
         }
+        if let Event::BatchTimeout(t) = &event {
+            if !self.batch_timeout_times.insert(*t) {
+                return;
+            }
+        }
         self.queue.push(event);
     }
     fn pop(&mut self) -> Option<Event> {
@@ -82,6 +185,9 @@ impl EventQueue {
         if let Some(Event::UpdateClassicalTasks(t)) = &event {
             self.update_classical_task_times.remove(t);
         }
+        if let Some(Event::BatchTimeout(t)) = &event {
+            self.batch_timeout_times.remove(t);
+        }
         event
     }
     fn len(&self) -> usize {
@@ -89,26 +195,6 @@ impl EventQueue {
     }
 }
 
-enum QuantumSchedulePolicy {
-    Fifo,
-    Lifo,
-    Random,
-    Weighted,
-}
-
-impl FromStr for QuantumSchedulePolicy {
-    type Err = anyhow::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "fifo" => Ok(QuantumSchedulePolicy::Fifo),
-            "lifo" => Ok(QuantumSchedulePolicy::Lifo),
-            "random" => Ok(QuantumSchedulePolicy::Random),
-            "weighted" => Ok(QuantumSchedulePolicy::Weighted),
-            _ => anyhow::bail!("cannot parse '{}' as quantum schedule policy", s),
-        }
-    }
-}
-
 #[derive(Debug)]
 pub struct Config {
     /// The seed to initialize pseudo-random number generators.
@@ -125,6 +211,57 @@ pub struct Config {
     pub num_serverless_workers: usize,
     /// The number of quantum computers.
     pub num_quantum_computers: usize,
+    /// The specifications of the quantum computers, which override
+    /// `num_quantum_computers` if not empty.
+    pub quantum_computers: String,
+    /// The policy to select the quantum computer executing a task.
+    pub qpu_selection_policy: String,
+    /// The organization of the queues of pending quantum tasks.
+    pub quantum_queue_mode: String,
+    /// The session policies of jobs, per priority (can be empty).
+    pub sessions: String,
+    /// The policy to execute quantum tasks in batches (can be empty).
+    pub batch: String,
+    /// The policy to execute concurrently quantum tasks on the same
+    /// quantum computer (can be empty).
+    pub multi_programming: String,
+    /// The downtimes of the quantum computers (can be empty).
+    pub downtimes: String,
+    /// What happens to the quantum tasks in execution when a downtime begins.
+    pub downtime_policy: String,
+    /// The model of the random failures of the quantum computers (can be empty).
+    pub failures: String,
+    /// The maximum number of times the quantum tasks of a job are executed
+    /// again after a failure before the job fails.
+    pub retry_budget: u32,
+    /// The distribution of the cold-start latency of serverless function
+    /// instances (can be empty).
+    pub cold_start: String,
+    /// The time a serverless function instance is kept warm after executing
+    /// a classical task, in s.
+    pub keep_alive: f64,
+    /// The policy to scale the number of serverless workers (can be empty).
+    pub autoscaler: String,
+    /// The minimum number of serverless workers, with autoscaling.
+    pub min_workers: usize,
+    /// The maximum number of serverless workers, with autoscaling.
+    pub max_workers: usize,
+    /// The time needed to add serverless workers, in s.
+    pub scale_up_delay: f64,
+    /// The interval between consecutive autoscaling decisions, in s.
+    pub autoscaler_interval: f64,
+    /// The policy to share the serverless workers among classical tasks.
+    pub classical_scheduler: String,
+    /// The distribution of the latency to submit a quantum task to a
+    /// remote quantum computer (can be empty).
+    pub submission_latency: String,
+    /// The distribution of the latency to retrieve the result of a quantum
+    /// task from a remote quantum computer (can be empty).
+    pub retrieval_latency: String,
+    /// The sizes of the payloads submitted and retrieved (can be empty).
+    pub payload: String,
+    /// The bandwidth of the link to the remote quantum computers, in Mb/s.
+    pub bandwidth: f64,
     /// The maximum queue length for classical tasks.
     pub max_classical_tasks: usize,
     /// The maximum queue length for quantum tasks.
@@ -133,21 +270,35 @@ pub struct Config {
     pub quantum_schedule_policy: String,
     /// The job type.
     pub job_type: String,
+    /// The number of quantum tasks per iteration of VQE and QAOA jobs.
+    pub quantum_tasks: String,
+    /// The model of the number of iterations of VQE and QAOA jobs.
+    pub convergence: String,
+    /// True if the policies cannot see the true size of VQE and QAOA jobs.
+    pub hide_job_sizes: bool,
+    /// The variability of the iterations of VQE and QAOA jobs.
+    pub iteration_variability: String,
     /// The job priorities.
     pub priorities: String,
+    /// The relative deadlines of jobs, per priority (can be empty).
+    pub deadlines: String,
     /// Save iteration durations.
     pub save_iteration_durations: bool,
+    /// Save a record of every job completed.
+    pub save_jobs: bool,
+    /// The writer of the trace of events and tasks, if any.
+    pub trace: Option<crate::trace::SharedTraceWriter>,
     /// Target durationg of quantum iterations (can be empty).
     pub target_dur_qc_avg: std::collections::BTreeMap<u16, f64>,
 }
 
 impl Config {
     pub fn header() -> String {
-        "seed,duration,job_interarrival,warmup_period,worker_capacity,num_serverless_workers,num_quantum_computers,max_classical_tasks,max_quantum_tasks,quantum_schedule_policy,job_type,priorities".to_string()
+        "seed,duration,job_interarrival,warmup_period,worker_capacity,num_serverless_workers,num_quantum_computers,max_classical_tasks,max_quantum_tasks,quantum_schedule_policy,job_type,priorities,deadlines,quantum_computers,qpu_selection_policy,quantum_queue_mode,sessions,batch,multi_programming,downtimes,downtime_policy,failures,retry_budget,cold_start,keep_alive,autoscaler,min_workers,max_workers,scale_up_delay,autoscaler_interval,classical_scheduler,submission_latency,retrieval_latency,payload,bandwidth,quantum_tasks,convergence,hide_job_sizes,iteration_variability".to_string()
     }
     pub fn to_csv(&self) -> String {
         format!(
-            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
             self.seed,
             self.duration,
             self.job_interarrival,
@@ -159,7 +310,34 @@ impl Config {
             self.max_quantum_tasks,
             self.quantum_schedule_policy,
             self.job_type,
-            self.priorities
+            self.priorities,
+            self.deadlines,
+            self.quantum_computers,
+            self.qpu_selection_policy,
+            self.quantum_queue_mode,
+            self.sessions,
+            self.batch,
+            self.multi_programming,
+            self.downtimes,
+            self.downtime_policy,
+            self.failures,
+            self.retry_budget,
+            self.cold_start,
+            self.keep_alive,
+            self.autoscaler,
+            self.min_workers,
+            self.max_workers,
+            self.scale_up_delay,
+            self.autoscaler_interval,
+            self.classical_scheduler,
+            self.submission_latency,
+            self.retrieval_latency,
+            self.payload,
+            self.bandwidth,
+            self.quantum_tasks,
+            self.convergence,
+            self.hide_job_sizes,
+            self.iteration_variability
         )
     }
 }
@@ -168,15 +346,43 @@ pub struct Simulation {
     // internal data structures
     job_factory: crate::job::JobFactory,
     job_interarrival_rng: rand::rngs::StdRng,
-    vqe_num_qubits_rng: rand::rngs::StdRng,
+    job_type_rng: rand::rngs::StdRng,
     active_jobs: std::collections::HashMap<u64, crate::job::Job>,
     active_classical_tasks: Vec<crate::task::Task>,
+    starting_classical_tasks: Vec<crate::task::Task>,
+    transferring_tasks: Vec<crate::task::Task>,
+    jobs: Vec<String>,
+    trace: Option<crate::trace::Trace>,
     pending_quantum_tasks: Vec<crate::task::Task>,
     active_quantum_tasks: Vec<crate::task::Task>,
-    quantum_schedule_policy: QuantumSchedulePolicy,
-    quantum_schedule_rng: rand::rngs::StdRng,
-    num_qubits: Vec<u16>,
+    quantum_computers: Vec<crate::quantum_computer::QuantumComputer>,
+    qpu_busy_times: Vec<u64>,
+    qpu_busy_since: Vec<Option<u64>>,
+    qpu_selection_policy: crate::quantum_computer::QpuSelectionPolicy,
+    qpu_selection_rng: rand::rngs::StdRng,
+    quantum_queue_mode: crate::quantum_computer::QueueMode,
+    qpu_sessions: Vec<Option<Session>>,
+    next_session_id: u64,
+    batch_policy: Option<crate::quantum_computer::BatchPolicy>,
+    multi_programming_policy: Option<crate::quantum_computer::MultiProgrammingPolicy>,
+    downtimes: Vec<Vec<crate::quantum_computer::Downtime>>,
+    downtime_policy: crate::quantum_computer::DowntimePolicy,
+    qpu_outages: Vec<u32>,
+    failure_model: Option<crate::quantum_computer::FailureModel>,
+    failure_rng: rand::rngs::StdRng,
+    cold_start: Option<crate::latency::LatencyDistribution>,
+    cold_start_rng: rand::rngs::StdRng,
+    network: Option<crate::latency::NetworkModel>,
+    network_rng: rand::rngs::StdRng,
+    autoscaler: Option<Box<dyn crate::autoscaler::Autoscaler>>,
+    num_workers: usize,
+    num_pending_workers: usize,
+    classical_scheduler: crate::classical_scheduler::ClassicalScheduler,
+    quantum_scheduler: Box<dyn crate::quantum_scheduler::QuantumScheduler>,
+    job_types: Vec<crate::job::JobType>,
     priorities: Vec<u16>,
+    deadlines: std::collections::HashMap<u16, crate::job::RelativeDeadline>,
+    sessions: std::collections::HashMap<u16, crate::quantum_computer::SessionPolicy>,
 
     // configuration
     config: Config,
@@ -184,28 +390,31 @@ pub struct Simulation {
 
 impl Simulation {
     pub fn new(config: Config) -> anyhow::Result<Self> {
+        let job_types = crate::job::parse_job_types(&config.job_type)?;
+        let job_factory = crate::job::JobFactory::new(
+            config.seed,
+            &job_types,
+            &config.target_dur_qc_avg,
+            crate::job::QuantumTasks::from_str(&config.quantum_tasks)?,
+            crate::job::Convergence::from_str(&config.convergence)?,
+            config.hide_job_sizes,
+            crate::job::IterationVariability::from_str(&config.iteration_variability)?,
+        )?;
+        Self::with_job_factory(config, job_factory)
+    }
+
+    /// Create a simulation whose jobs are created by a given factory.
+    fn with_job_factory(
+        mut config: Config,
+        job_factory: crate::job::JobFactory,
+    ) -> anyhow::Result<Self> {
         anyhow::ensure!(config.duration > 0.0, "vanishing duration");
         anyhow::ensure!(
             config.job_interarrival > 0.0,
             "vanishing avg job interarrival time"
         );
 
-        let mut tokens = config.job_type.split(';').clone().collect::<Vec<&str>>();
-        anyhow::ensure!(!tokens.is_empty(), "invalid empty job type");
-        anyhow::ensure!(tokens[0].to_ascii_lowercase() == "vqe", "invalid job type");
-        anyhow::ensure!(
-            tokens.len() > 1,
-            "too few qubits specified for VQE job type"
-        );
-        tokens.remove(0);
-        let num_qubits = tokens
-            .iter()
-            .filter_map(|x| x.parse::<u16>().ok())
-            .collect::<Vec<u16>>();
-        anyhow::ensure!(
-            tokens.len() == num_qubits.len(),
-            "cannot parse number of qubits in VQE job type"
-        );
+        let job_types = crate::job::parse_job_types(&config.job_type)?;
 
         let tokens = config.priorities.split(';').clone().collect::<Vec<&str>>();
         anyhow::ensure!(!tokens.is_empty(), "invalid empty priorities");
@@ -216,6 +425,77 @@ impl Simulation {
             .collect::<Vec<u16>>();
         anyhow::ensure!(tokens.len() == priorities.len(), "cannot parse priorities");
 
+        let deadlines = Self::parse_per_priority(&config.deadlines, &priorities, "deadline")?;
+        let sessions = Self::parse_per_priority(&config.sessions, &priorities, "session")?;
+        let batch_policy = if config.batch.is_empty() {
+            None
+        } else {
+            Some(crate::quantum_computer::BatchPolicy::from_str(
+                &config.batch,
+            )?)
+        };
+        anyhow::ensure!(
+            sessions.is_empty() || batch_policy.is_none(),
+            "sessions cannot be used with batch execution"
+        );
+        let multi_programming_policy = if config.multi_programming.is_empty() {
+            None
+        } else {
+            Some(crate::quantum_computer::MultiProgrammingPolicy::from_str(
+                &config.multi_programming,
+            )?)
+        };
+        anyhow::ensure!(
+            multi_programming_policy.is_none() || (sessions.is_empty() && batch_policy.is_none()),
+            "multi-programming cannot be used with sessions or batch execution"
+        );
+        anyhow::ensure!(
+            multi_programming_policy.is_none() || !config.quantum_computers.is_empty(),
+            "multi-programming requires the number of qubits of the quantum computers"
+        );
+
+        let quantum_computers = if config.quantum_computers.is_empty() {
+            (0..config.num_quantum_computers)
+                .map(|i| crate::quantum_computer::QuantumComputer::unlimited(&format!("qc{}", i)))
+                .collect::<Vec<crate::quantum_computer::QuantumComputer>>()
+        } else {
+            crate::quantum_computer::parse_quantum_computers(&config.quantum_computers)?
+        };
+        config.num_quantum_computers = quantum_computers.len();
+        for job_type in &job_types {
+            anyhow::ensure!(
+                quantum_computers
+                    .iter()
+                    .any(|x| x.fits(job_type.num_qubits())),
+                "no quantum computer can execute circuits with {} qubits",
+                job_type.num_qubits()
+            );
+        }
+        let downtimes = if config.downtimes.is_empty() {
+            vec![vec![]; quantum_computers.len()]
+        } else {
+            crate::quantum_computer::parse_downtimes(
+                &config.downtimes,
+                quantum_computers.len(),
+                to_nanoseconds(config.duration),
+            )?
+        };
+
+        let autoscaler = if config.autoscaler.is_empty() {
+            None
+        } else {
+            anyhow::ensure!(
+                config.min_workers <= config.num_serverless_workers
+                    && config.num_serverless_workers <= config.max_workers,
+                "the number of serverless workers must be within the autoscaling limits"
+            );
+            anyhow::ensure!(
+                config.autoscaler_interval > 0.0,
+                "vanishing autoscaler interval"
+            );
+            Some(crate::autoscaler::make_autoscaler(&config.autoscaler)?)
+        };
+
         let mut seed_cnt = 0_u64;
         let mut next_seed = || {
             seed_cnt += 1;
@@ -223,23 +503,131 @@ impl Simulation {
         };
 
         Ok(Self {
-            job_factory: crate::job::JobFactory::new(config.seed, &config.target_dur_qc_avg)?,
+            job_factory,
             job_interarrival_rng: rand::rngs::StdRng::seed_from_u64(next_seed()),
-            vqe_num_qubits_rng: rand::rngs::StdRng::seed_from_u64(next_seed()),
+            job_type_rng: rand::rngs::StdRng::seed_from_u64(next_seed()),
             active_jobs: std::collections::HashMap::new(),
             active_classical_tasks: vec![],
+            starting_classical_tasks: vec![],
+            transferring_tasks: vec![],
+            jobs: vec![],
+            trace: config
+                .trace
+                .as_ref()
+                .map(|writer| crate::trace::Trace::new(config.seed, writer.clone())),
             pending_quantum_tasks: vec![],
             active_quantum_tasks: vec![],
-            quantum_schedule_policy: QuantumSchedulePolicy::from_str(
+            qpu_busy_times: vec![0; quantum_computers.len()],
+            qpu_busy_since: vec![None; quantum_computers.len()],
+            qpu_sessions: vec![None; quantum_computers.len()],
+            next_session_id: 0,
+            batch_policy,
+            multi_programming_policy,
+            downtimes,
+            downtime_policy: crate::quantum_computer::DowntimePolicy::from_str(
+                &config.downtime_policy,
+            )?,
+            qpu_outages: vec![0; quantum_computers.len()],
+            failure_model: if config.failures.is_empty() {
+                None
+            } else {
+                Some(crate::quantum_computer::FailureModel::from_str(
+                    &config.failures,
+                )?)
+            },
+            quantum_computers,
+            quantum_scheduler: crate::quantum_scheduler::make_quantum_scheduler(
                 &config.quantum_schedule_policy,
+                next_seed(),
+            )?,
+            qpu_selection_policy: crate::quantum_computer::QpuSelectionPolicy::from_str(
+                &config.qpu_selection_policy,
+            )?,
+            qpu_selection_rng: rand::rngs::StdRng::seed_from_u64(next_seed()),
+            failure_rng: rand::rngs::StdRng::seed_from_u64(next_seed()),
+            cold_start: if config.cold_start.is_empty() {
+                None
+            } else {
+                Some(crate::latency::LatencyDistribution::from_str(
+                    &config.cold_start,
+                )?)
+            },
+            cold_start_rng: rand::rngs::StdRng::seed_from_u64(next_seed()),
+            network: crate::latency::NetworkModel::new(
+                &config.submission_latency,
+                &config.retrieval_latency,
+                &config.payload,
+                config.bandwidth,
             )?,
-            quantum_schedule_rng: rand::rngs::StdRng::seed_from_u64(next_seed()),
-            num_qubits,
+            network_rng: rand::rngs::StdRng::seed_from_u64(next_seed()),
+            autoscaler,
+            num_workers: config.num_serverless_workers,
+            num_pending_workers: 0,
+            classical_scheduler: crate::classical_scheduler::ClassicalScheduler::from_str(
+                &config.classical_scheduler,
+            )?,
+            quantum_queue_mode: crate::quantum_computer::QueueMode::from_str(
+                &config.quantum_queue_mode,
+            )?,
+            job_types,
             priorities,
+            deadlines,
+            sessions,
             config,
         })
     }
 
+    /// Parse a specification per priority, which can be either empty
+    /// (no values), a single value applied to jobs of any priority,
+    /// or a list of `priority:value` separated by semicolons.
+    fn parse_per_priority<T>(
+        spec: &str,
+        priorities: &[u16],
+        what: &str,
+    ) -> anyhow::Result<std::collections::HashMap<u16, T>>
+    where
+        T: FromStr<Err = anyhow::Error> + Clone,
+    {
+        let mut ret = std::collections::HashMap::new();
+        if spec.is_empty() {
+            return Ok(ret);
+        }
+        if !spec.contains(':') {
+            let value = T::from_str(spec)?;
+            for priority in priorities {
+                ret.insert(*priority, value.clone());
+            }
+            return Ok(ret);
+        }
+        for token in spec.split(';') {
+            let (priority, value) = token.split_once(':').ok_or(anyhow::anyhow!(
+                "invalid {} specification: {}",
+                what,
+                token
+            ))?;
+            let priority = priority
+                .parse::<u16>()
+                .map_err(|_| anyhow::anyhow!("invalid priority in {}: {}", what, token))?;
+            anyhow::ensure!(
+                priorities.contains(&priority),
+                "{} specified for unknown priority: {}",
+                what,
+                priority
+            );
+            ret.insert(priority, T::from_str(value)?);
+        }
+        Ok(ret)
+    }
+
+    /// Replace the quantum scheduler created from the configuration with
+    /// a user-defined one.
+    pub fn set_quantum_scheduler(
+        &mut self,
+        quantum_scheduler: Box<dyn crate::quantum_scheduler::QuantumScheduler>,
+    ) {
+        self.quantum_scheduler = quantum_scheduler;
+    }
+
     /// Run a simulation.
     pub fn run(&mut self) -> crate::output::Output {
         // outputs
@@ -253,6 +641,25 @@ impl Simulation {
             self.config.warmup_period,
         )));
         events.push(Event::ExperimentEnd(to_nanoseconds(self.config.duration)));
+        for (qpu_id, downtimes) in self.downtimes.iter().enumerate() {
+            for downtime in downtimes {
+                events.push(Event::DowntimeStart(downtime.start, qpu_id));
+                events.push(Event::DowntimeEnd(downtime.end, qpu_id));
+            }
+        }
+        if self.autoscaler.is_some() {
+            events.push(Event::AutoscalerTick(to_nanoseconds(
+                self.config.autoscaler_interval,
+            )));
+        }
+        if let Some(failure_model) = self.failure_model {
+            for qpu_id in 0..self.quantum_computers.len() {
+                events.push(Event::QpuFailure(
+                    failure_model.time_to_failure(&mut self.failure_rng),
+                    qpu_id,
+                ));
+            }
+        }
         for i in 1..100 {
             events.push(Event::Progress(
                 to_nanoseconds(i as f64 * self.config.duration / 100.0),
@@ -270,10 +677,66 @@ impl Simulation {
         let mut num_job_accepted = 0;
         let mut num_job_dropped = 0;
         let mut num_events = 0;
-        series.set_header("job_time", "num_qubits,priority");
+        let mut num_session_timeouts = 0;
+        let mut num_job_aborted = 0;
+        let mut num_job_failed = 0;
+        let mut num_retries = 0;
+        let mut lost_quantum_time = 0;
+        for qpu_id in 0..self.quantum_computers.len() {
+            single.time_avg(&qpu_utilization_metric(qpu_id), 0, 0.0);
+            if self.quantum_queue_mode != crate::quantum_computer::QueueMode::Central {
+                single.time_avg(&qpu_queue_len_metric(qpu_id), 0, 0.0);
+            }
+            if !self.config.downtimes.is_empty() || self.failure_model.is_some() {
+                single.time_avg(&qpu_availability_metric(qpu_id), 0, 1.0);
+            }
+        }
+        if !self.sessions.is_empty() {
+            single.time_avg("reserved_quantum_computers", 0, 0.0);
+        }
+        if self.batch_policy.is_some() {
+            single.sample_avg_register("batch_size");
+        }
+        if self.multi_programming_policy.is_some() {
+            single.sample_avg_register("multi_programming_level");
+            single.sample_avg_register("fidelity");
+        }
+        for name in [
+            "job_time",
+            "classical_service_time",
+            "classical_waiting_time",
+            "quantum_queueing_time",
+            "quantum_service_time",
+        ] {
+            series.set_header(name, crate::job::LABEL_HEADER);
+        }
+        if self.network.is_some() {
+            series.set_header("network_time", crate::job::LABEL_HEADER);
+        }
+        if !self.deadlines.is_empty() {
+            series.set_header("lateness", crate::job::LABEL_HEADER);
+            for job_type in &self.job_types {
+                for priority in self.deadlines.keys() {
+                    single.sample_avg_register(&deadline_miss_metric(&job_type.label(*priority)));
+                }
+            }
+        }
+        if self.autoscaler.is_some() {
+            single.time_avg("serverless_workers", 0, self.num_workers as f64);
+        }
+        if self.classical_scheduler.is_dedicated() {
+            single.time_avg("waiting_classical_tasks", 0, 0.0);
+        }
+        if self.cold_start.is_some() {
+            for job_type in &self.job_types {
+                for priority in &self.priorities {
+                    single.sample_avg_register(&cold_start_metric(&job_type.label(*priority)));
+                }
+            }
+        }
         if self.config.save_iteration_durations {
-            series.set_header("qc_iter_dur", "num_qubits,priority");
-            series.set_header("classical_dur", "num_qubits,priority");
+            series.set_header("qc_iter_dur", crate::job::LABEL_HEADER);
+            series.set_header("classical_dur", crate::job::LABEL_HEADER);
         }
 
         // simulation loop
@@ -287,6 +750,13 @@ impl Simulation {
 
                 log::debug!("XXX {} {}", now, now - last_time);
 
+                let (name, args) = event.describe();
+                self.trace(crate::trace::TraceRecord::Event {
+                    time: now,
+                    name,
+                    args,
+                });
+
                 // make sure we never go back in time
                 assert!(now >= last_time);
                 last_time = now;
@@ -299,39 +769,49 @@ impl Simulation {
                     Event::JobStart(time_arrival) => {
                         assert_eq!(time_arrival, now);
 
-                        if self.active_classical_tasks.len() < self.config.max_classical_tasks
+                        if self.active_classical_tasks.len() + self.starting_classical_tasks.len()
+                            < self.config.max_classical_tasks
                             && self.pending_quantum_tasks.len() < self.config.max_quantum_tasks
                         {
                             // create a new job and draw randomly its lifetime
-                            let num_qubits = self
-                                .num_qubits
-                                .choose(&mut self.vqe_num_qubits_rng)
-                                .unwrap();
-                            let priority = self
-                                .priorities
-                                .choose(&mut self.vqe_num_qubits_rng)
-                                .unwrap();
-                            let job = self.job_factory.make(
-                                crate::job::JobType::Vqe(*num_qubits),
-                                *priority,
-                                now,
-                            );
+                            let job_type = self
+                                .job_types
+                                .choose(&mut self.job_type_rng)
+                                .unwrap()
+                                .clone();
+                            let priority = self.priorities.choose(&mut self.job_type_rng).unwrap();
+                            let job = self.job_factory.make(job_type.clone(), *priority, now);
                             log::debug!("A {} {:?}", now, job);
 
-                            // manage the job's initial task
                             if let Ok(mut job) = job {
-                                if let Some(event) =
-                                    self.manage_task(now, job.next_task(now).unwrap(), &mut single)
-                                {
-                                    events.push(event);
+                                if let Some(deadline) = self.deadlines.get(priority) {
+                                    job.set_deadline(*deadline, self.config.worker_capacity);
                                 }
 
                                 // add the job the map of active ones
-                                self.active_jobs.insert(job.job_id, job);
+                                // and manage its initial tasks
+                                let job_id = job.job_id;
+                                self.active_jobs.insert(job_id, job);
+                                let new_task_res = self.new_task_for_job(
+                                    now,
+                                    job_id,
+                                    false,
+                                    &mut series,
+                                    &mut single,
+                                );
+                                if new_task_res.0 {
+                                    let res = self.active_jobs.remove(&job_id);
+                                    assert!(res.is_some());
+                                } else {
+                                    for event in new_task_res.1 {
+                                        events.push(event);
+                                    }
+                                }
                             } else {
                                 log::warn!(
-                                    "error when creating a job with {} qubits and priority {}",
-                                    num_qubits,
+                                    "error when creating a {} job with {} qubits and priority {}",
+                                    job_type.algorithm(),
+                                    job_type.num_qubits(),
                                     priority
                                 );
                             }
@@ -357,36 +837,102 @@ impl Simulation {
                         break 'main_loop;
                     }
                     Event::Progress(_, percentage) => {
-                        assert!(
-                            self.active_jobs.len()
-                                == (self.active_classical_tasks.len()
-                                    + self.active_quantum_tasks.len()
-                                    + self.pending_quantum_tasks.len())
+                        // every active job has at least one task somewhere
+                        assert_eq!(
+                            self.active_jobs.len(),
+                            self.active_classical_tasks
+                                .iter()
+                                .chain(self.starting_classical_tasks.iter())
+                                .chain(self.transferring_tasks.iter())
+                                .chain(self.active_quantum_tasks.iter())
+                                .chain(self.pending_quantum_tasks.iter())
+                                .map(|task| task.job_id)
+                                .collect::<std::collections::BTreeSet<u64>>()
+                                .len()
                         );
                         log::info!("completed {}% ({} active jobs, {} classical tasks, {}/{} quantum tasks", percentage, self.active_jobs.len(), self.active_classical_tasks.len(), self.active_quantum_tasks.len(), self.pending_quantum_tasks.len());
                     }
                     Event::QuantumIterationEnd(_) => {
                         self.log_internals("Q", now);
 
-                        // find the completed task
-                        assert!(!self.active_quantum_tasks.is_empty());
-                        let pos = self
-                            .active_quantum_tasks
-                            .iter()
-                            .position(|task| {
-                                if let crate::task::TaskType::Quantum(residual) = task.task_type {
-                                    residual == (now - task.last_update)
-                                } else {
-                                    false
-                                }
-                            })
-                            .unwrap();
+                        // find the completed task, which may not exist
+                        // if it has been interrupted by a downtime or, with
+                        // multi-programming, if its end has been postponed
+                        let pos = self.active_quantum_tasks.iter().position(|task| {
+                            if let crate::task::TaskType::Quantum(residual) = task.task_type {
+                                residual == (now - task.last_update)
+                            } else {
+                                false
+                            }
+                        });
+                        let Some(pos) = pos else {
+                            assert!(
+                                self.failure_model.is_some()
+                                    || self.multi_programming_policy.is_some()
+                                    || self.downtime_policy
+                                        != crate::quantum_computer::DowntimePolicy::Finish
+                            );
+                            continue;
+                        };
                         let completed_task = self.active_quantum_tasks.swap_remove(pos);
+                        self.trace(crate::trace::TraceRecord::TaskEnd {
+                            time: now,
+                            job_id: completed_task.job_id,
+                            branch: completed_task.branch,
+                            quantum: true,
+                            resource: completed_task.resource,
+                            completed: true,
+                        });
                         single.time_avg(
                             "active_quantum_tasks",
                             now,
                             self.active_quantum_tasks.len() as f64,
                         );
+                        let qpu_id = completed_task.resource.unwrap();
+                        self.update_busy_time(now, qpu_id, &mut single);
+                        let num_left = self
+                            .active_quantum_tasks
+                            .iter()
+                            .filter(|task| task.resource == Some(qpu_id))
+                            .count();
+                        if num_left > 0 {
+                            for event in
+                                self.share_quantum_computer(now, qpu_id, num_left, num_left - 1)
+                            {
+                                events.push(event);
+                            }
+                        }
+                        let job = self.active_jobs.get_mut(&completed_task.job_id).unwrap();
+                        if job.quantum_task_completed(now - completed_task.last_update) {
+                            // the tasks of a stage are executed in parallel,
+                            // hence only the last one completed is accounted
+                            // for in the breakdown of the job time, so that
+                            // the stage lasts from its start until now
+                            job.network_time += completed_task.start_time - job.stage_start_time();
+                            job.quantum_time += now - completed_task.start_time;
+                            job.quantum_service_time += now - completed_task.last_update;
+                        }
+                        let has_quantum_iterations_left = job.has_quantum_iterations_left();
+                        let priority = job.priority;
+
+                        // release the quantum computer reserved for the
+                        // job if there are no more quantum tasks,
+                        // otherwise start the idle timeout, if any
+                        if let Some(session_qpu_id) = self.session_of(completed_task.job_id) {
+                            if !has_quantum_iterations_left {
+                                self.close_session(now, session_qpu_id, &mut single);
+                            } else if let Some(idle_timeout) =
+                                self.sessions.get(&priority).unwrap().idle_timeout
+                            {
+                                let session = self.qpu_sessions[session_qpu_id].as_mut().unwrap();
+                                session.idle_since = now;
+                                events.push(Event::SessionTimeout(
+                                    now + idle_timeout,
+                                    session_qpu_id,
+                                    session.session_id,
+                                ));
+                            }
+                        }
                         if self.config.save_iteration_durations {
                             series.add(
                                 "qc_iter_dur",
@@ -398,135 +944,192 @@ impl Simulation {
                         let new_task_res = self.new_task_for_job(
                             now,
                             completed_task.job_id,
+                            true,
                             &mut series,
                             &mut single,
                         );
                         if new_task_res.0 {
                             let res = self.active_jobs.remove(&completed_task.job_id);
                             assert!(res.is_some());
-                        } else if let Some(event) = new_task_res.1 {
-                            events.push(event);
+                        } else {
+                            for event in new_task_res.1 {
+                                events.push(event);
+                            }
                         }
 
-                        // if there is at least one pending quantum task put
-                        // it into action
-                        if let Some(mut new_task) = self.schedule_next_quantum_task() {
-                            new_task.last_update = now;
-                            if let crate::task::TaskType::Quantum(duration) = new_task.task_type {
-                                events.push(Event::QuantumIterationEnd(now + duration));
+                        // put into action as many pending quantum tasks
+                        // as possible
+                        for event in self.dispatch_quantum_tasks(now, &mut single) {
+                            events.push(event);
+                        }
+                    }
+                    Event::SessionTimeout(_, qpu_id, session_id) => {
+                        // release the quantum computer only if the session
+                        // is still the same, the job is not using it, and
+                        // this is the timeout of its last quantum task
+                        if self.qpu_sessions[qpu_id].is_some_and(|session| {
+                            session.session_id == session_id
+                                && now >= session.idle_since + self.idle_timeout_of(session.job_id)
+                        }) && self.is_quantum_computer_idle(qpu_id)
+                        {
+                            num_session_timeouts += 1;
+                            self.close_session(now, qpu_id, &mut single);
+                            for event in self.dispatch_quantum_tasks(now, &mut single) {
+                                events.push(event);
                             }
-                            self.active_quantum_tasks.push(new_task);
-                            single.time_avg(
-                                "pending_quantum_tasks",
-                                now,
-                                self.pending_quantum_tasks.len() as f64,
-                            );
-                            single.time_avg(
-                                "active_quantum_tasks",
-                                now,
-                                self.active_quantum_tasks.len() as f64,
-                            );
                         }
                     }
-                    Event::UpdateClassicalTasks(_) => {
-                        self.log_internals("C", now);
-
-                        // count the active tasks since the last update
-                        let num_tasks = self
-                            .active_classical_tasks
-                            .iter()
-                            .map(|x| if x.last_update == now { 0 } else { 1 })
-                            .sum::<u64>();
-                        assert!(num_tasks <= self.active_classical_tasks.len() as u64);
-
-                        // processing capacity during the last period, in ops/s
-                        let capacity = if num_tasks == 0 {
-                            None
-                        } else {
-                            Some(std::cmp::min(
-                                self.config.worker_capacity,
-                                self.config.num_serverless_workers as u64
-                                    * self.config.worker_capacity
-                                    / num_tasks,
-                            ))
-                        };
-
-                        // update the residual of all the tasks
-                        // and find which tasks are complete (if any)
-                        let mut residuals = vec![];
-                        let mut finished_tasks_start_times = vec![];
-                        let mut finished_task_job_ids = std::collections::HashSet::new();
-                        let capacity_ratio = capacity.map(|capacity| capacity as f64 / 1e9_f64);
-                        for task in &mut self.active_classical_tasks {
-                            let num_ops = if let Some(capacity_ratio) = capacity_ratio {
-                                ((now - task.last_update) as f64 * capacity_ratio).ceil() as u64
-                            } else {
-                                0
-                            };
-                            task.last_update = now;
-                            if let crate::task::TaskType::Classical(residual) = &mut task.task_type
-                            {
-                                assert!(*residual >= num_ops);
-                                *residual -= num_ops;
-                                if *residual == 0 {
-                                    finished_tasks_start_times.push((task.job_id, task.start_time));
-                                    finished_task_job_ids.insert(task.job_id);
+                    Event::DowntimeStart(_, qpu_id) => {
+                        self.quantum_computer_offline(now, qpu_id, &mut single);
+                        if self.downtime_policy != crate::quantum_computer::DowntimePolicy::Finish {
+                            for task in self.interrupt_quantum_tasks(now, qpu_id, &mut single) {
+                                lost_quantum_time += now - task.last_update;
+                                if !self.active_jobs.contains_key(&task.job_id) {
+                                    // another task of the same job was interrupted
+                                    continue;
+                                }
+                                if self.downtime_policy
+                                    == crate::quantum_computer::DowntimePolicy::Abort
+                                {
+                                    let (lost, new_events) =
+                                        self.remove_job(now, task.job_id, &mut single);
+                                    lost_quantum_time += lost;
+                                    for event in new_events {
+                                        events.push(event);
+                                    }
+                                    num_job_aborted += 1;
                                 } else {
-                                    residuals.push(*residual);
+                                    for event in self.requeue_quantum_task(now, task, &mut single) {
+                                        events.push(event);
+                                    }
                                 }
                             }
                         }
-
-                        // add a performance sample for the task duration
-                        if self.config.save_iteration_durations {
-                            for (job_id, start_time) in finished_tasks_start_times {
-                                series.add(
-                                    "classical_dur",
-                                    &self.active_jobs.get(&job_id).unwrap().label,
-                                    to_seconds(now - start_time),
-                                );
+                    }
+                    Event::DowntimeEnd(_, qpu_id) => {
+                        for event in self.quantum_computer_online(now, qpu_id, &mut single) {
+                            events.push(event);
+                        }
+                    }
+                    Event::QpuFailure(_, qpu_id) => {
+                        let failure_model = self.failure_model.unwrap();
+                        self.quantum_computer_offline(now, qpu_id, &mut single);
+                        for task in self.interrupt_quantum_tasks(now, qpu_id, &mut single) {
+                            lost_quantum_time += now - task.last_update;
+                            let Some(job) = self.active_jobs.get_mut(&task.job_id) else {
+                                // another task of the same job was interrupted
+                                continue;
+                            };
+                            if job.num_retries < self.config.retry_budget {
+                                job.num_retries += 1;
+                                num_retries += 1;
+                                for event in self.requeue_quantum_task(now, task, &mut single) {
+                                    events.push(event);
+                                }
+                            } else {
+                                let (lost, new_events) =
+                                    self.remove_job(now, task.job_id, &mut single);
+                                lost_quantum_time += lost;
+                                for event in new_events {
+                                    events.push(event);
+                                }
+                                num_job_failed += 1;
                             }
                         }
-
-                        if !residuals.is_empty() {
-                            // find the smallest residual, if there tasks that
-                            // are still active after this event is fully handled
-                            residuals.sort_unstable();
-                            let smallest_residual = residuals.first().unwrap();
-
-                            log::debug!(
-                                "YYY {} + {} = {}",
+                        events.push(Event::QpuRepair(
+                            now + failure_model.time_to_repair(&mut self.failure_rng),
+                            qpu_id,
+                        ));
+                    }
+                    Event::QpuRepair(_, qpu_id) => {
+                        let failure_model = self.failure_model.unwrap();
+                        for event in self.quantum_computer_online(now, qpu_id, &mut single) {
+                            events.push(event);
+                        }
+                        events.push(Event::QpuFailure(
+                            now + failure_model.time_to_failure(&mut self.failure_rng),
+                            qpu_id,
+                        ));
+                    }
+                    Event::BatchTimeout(_) => {
+                        for event in self.dispatch_quantum_tasks(now, &mut single) {
+                            events.push(event);
+                        }
+                    }
+                    Event::ColdStartEnd(_, job_id) => {
+                        let pos = self
+                            .starting_classical_tasks
+                            .iter()
+                            .position(|task| task.job_id == job_id)
+                            .unwrap();
+                        let mut new_task = self.starting_classical_tasks.swap_remove(pos);
+                        new_task.last_update = now;
+                        self.add_classical_task(now, new_task, &mut single);
+                        events.push(Event::UpdateClassicalTasks(now));
+                    }
+                    Event::HandOffEnd(_, job_id, branch) => {
+                        // the task may not exist if its job has been
+                        // aborted or has failed in the meanwhile
+                        let Some(pos) = self
+                            .transferring_tasks
+                            .iter()
+                            .position(|task| task.job_id == job_id && task.branch == branch)
+                        else {
+                            continue;
+                        };
+                        let mut new_task = self.transferring_tasks.swap_remove(pos);
+                        new_task.start_time = now;
+                        new_task.last_update = now;
+                        for event in self.manage_task(now, new_task, &mut single) {
+                            events.push(event);
+                        }
+                    }
+                    Event::AutoscalerTick(_) => {
+                        let desired = self
+                            .autoscaler
+                            .as_mut()
+                            .unwrap()
+                            .desired_workers(
                                 now,
-                                smallest_residual,
-                                now + smallest_residual
-                            );
-
-                            // create an event that is handled when the task with
-                            // the smallest residual finishes, unless there are new
-                            // tasks arriving that will mess the schedule
-                            events.push(Event::UpdateClassicalTasks(now + smallest_residual));
+                                self.active_classical_tasks.len(),
+                                self.num_workers,
+                            )
+                            .clamp(self.config.min_workers, self.config.max_workers);
+                        let expected = self.num_workers + self.num_pending_workers;
+                        if desired > expected {
+                            // new workers are available only after a delay
+                            self.num_pending_workers += desired - expected;
+                            events.push(Event::WorkersReady(
+                                now + to_nanoseconds(self.config.scale_up_delay),
+                                desired - expected,
+                            ));
+                        } else if desired < self.num_workers && self.num_pending_workers == 0 {
+                            for event in
+                                self.set_num_workers(now, desired, &mut series, &mut single)
+                            {
+                                events.push(event);
+                            }
                         }
-
-                        // remove the completed tasks from the active set
-                        self.active_classical_tasks
-                            .retain(|x| !finished_task_job_ids.contains(&x.job_id));
-                        single.time_avg(
-                            "active_classical_tasks",
+                        events.push(Event::AutoscalerTick(
+                            now + to_nanoseconds(self.config.autoscaler_interval),
+                        ));
+                    }
+                    Event::WorkersReady(_, num_workers) => {
+                        self.num_pending_workers -= num_workers;
+                        for event in self.set_num_workers(
                             now,
-                            self.active_classical_tasks.len() as f64,
-                        );
+                            self.num_workers + num_workers,
+                            &mut series,
+                            &mut single,
+                        ) {
+                            events.push(event);
+                        }
+                    }
+                    Event::UpdateClassicalTasks(_) => {
+                        self.log_internals("C", now);
 
-                        // for all jobs that are still active, schedule the
-                        // next task, otherwise remove the job from the active set
-                        for job_id in &finished_task_job_ids {
-                            let new_task_res =
-                                self.new_task_for_job(now, *job_id, &mut series, &mut single);
-                            if new_task_res.0 {
-                                let res = self.active_jobs.remove(job_id);
-                                assert!(res.is_some());
-                            } else if let Some(event) = new_task_res.1 {
-                                events.push(event);
-                            }
+                        for event in self.update_classical_tasks(now, &mut series, &mut single) {
+                            events.push(event);
                         }
                     }
                 }
@@ -538,59 +1141,908 @@ impl Simulation {
         single.one_time("execution_time", real_now.elapsed().as_secs_f64());
         single.one_time("num_job_accepted", num_job_accepted as f64);
         single.one_time("num_job_dropped", num_job_dropped as f64);
+        if !self.sessions.is_empty() {
+            single.one_time("num_session_timeouts", num_session_timeouts as f64);
+        }
+        if !self.config.downtimes.is_empty() {
+            single.one_time("num_job_aborted", num_job_aborted as f64);
+        }
+        if self.failure_model.is_some() {
+            single.one_time("num_job_failed", num_job_failed as f64);
+            single.one_time("num_retries", num_retries as f64);
+        }
+        if !self.config.downtimes.is_empty() || self.failure_model.is_some() {
+            single.one_time("lost_qpu_seconds", to_seconds(lost_quantum_time));
+        }
+
+        // write the rest of the trace, if any
+        if let Some(mut trace) = self.trace.take() {
+            if let Err(err) = trace.flush() {
+                log::error!("cannot write the trace: {}", err);
+            }
+        }
 
         // return the simulation output
         crate::output::Output {
             single,
             series,
+            jobs: std::mem::take(&mut self.jobs),
             config_csv: self.config.to_csv(),
         }
     }
 
-    fn schedule_next_quantum_task(&mut self) -> Option<crate::task::Task> {
-        if self.pending_quantum_tasks.is_empty() {
+    /// Update the residual of the classical tasks, based on the capacity of
+    /// the serverless workers since the last update, and schedule the next
+    /// task of the jobs whose classical tasks are complete.
+    /// Return the events to be scheduled.
+    fn update_classical_tasks(
+        &mut self,
+        now: u64,
+        series: &mut crate::output::OutputSeries,
+        single: &mut crate::output::OutputSingle,
+    ) -> Vec<Event> {
+        let mut events = vec![];
+
+        // count the active tasks since the last update
+        let num_tasks = self
+            .active_classical_tasks
+            .iter()
+            .map(|x| if x.last_update == now { 0 } else { 1 })
+            .sum::<u64>();
+        assert!(num_tasks <= self.active_classical_tasks.len() as u64);
+
+        // processing capacity during the last period, in ops/s
+        let capacity = (self.num_workers as u64 * self.config.worker_capacity)
+            .checked_div(num_tasks)
+            .map(|capacity| std::cmp::min(self.config.worker_capacity, capacity));
+
+        // update the residual of all the tasks
+        // and find which tasks are complete (if any)
+        let mut residuals = vec![];
+        let mut finished_tasks_start_times = vec![];
+        // the tasks of the jobs served by the same batch may complete
+        // together, hence they are handled in a deterministic order
+        let mut finished_task_job_ids = std::collections::BTreeSet::new();
+        let capacity_ratio = capacity.map(|capacity| capacity as f64 / 1e9_f64);
+        let dedicated = self.classical_scheduler.is_dedicated();
+
+        // with priority-aware sharing, the capacity of each task depends on
+        // the priorities of the jobs of the tasks active since the last update
+        let shares = if self.classical_scheduler
+            == crate::classical_scheduler::ClassicalScheduler::ProcessorSharing
+            || dedicated
+        {
             None
         } else {
-            let index = match self.quantum_schedule_policy {
-                QuantumSchedulePolicy::Fifo => 0,
-                QuantumSchedulePolicy::Lifo => self.pending_quantum_tasks.len() - 1,
-                QuantumSchedulePolicy::Random => {
-                    let indices: Vec<usize> = (0..self.pending_quantum_tasks.len()).collect();
-                    *indices.choose(&mut self.quantum_schedule_rng).unwrap()
-                }
-                QuantumSchedulePolicy::Weighted => {
-                    let indices: Vec<usize> = (0..self.pending_quantum_tasks.len()).collect();
-                    let mut weights = vec![];
-                    for task in &self.pending_quantum_tasks {
-                        weights.push(self.active_jobs.get(&task.job_id).unwrap().priority as u32);
+            let active = self
+                .active_classical_tasks
+                .iter()
+                .filter(|task| task.last_update != now)
+                .collect::<Vec<&crate::task::Task>>();
+            let shares = self.classical_scheduler.shares(
+                &active
+                    .iter()
+                    .map(|task| self.active_jobs.get(&task.job_id).unwrap().priority)
+                    .collect::<Vec<u16>>(),
+                (self.num_workers as u64 * self.config.worker_capacity) as f64,
+                self.config.worker_capacity as f64,
+            );
+            Some(
+                active
+                    .iter()
+                    .zip(shares)
+                    .map(|(task, share)| (task.job_id, share))
+                    .collect::<std::collections::HashMap<u64, f64>>(),
+            )
+        };
+
+        for task in &mut self.active_classical_tasks {
+            let num_ops = if let Some(shares) = &shares {
+                match shares.get(&task.job_id) {
+                    Some(share) => {
+                        ((now - task.last_update) as f64 * share / 1e9_f64).ceil() as u64
                     }
-                    assert_eq!(weights.len(), indices.len());
-                    weighted_rand::builder::WalkerTableBuilder::new(&weights)
-                        .build()
-                        .next()
+                    None => 0,
+                }
+            } else if dedicated {
+                // only the tasks holding a worker make progress
+                if task.resource.is_some() {
+                    ((now - task.last_update) as f64 * self.config.worker_capacity as f64 / 1e9_f64)
+                        .ceil() as u64
+                } else {
+                    0
                 }
+            } else if let Some(capacity_ratio) = capacity_ratio {
+                ((now - task.last_update) as f64 * capacity_ratio).ceil() as u64
+            } else {
+                0
             };
-            Some(self.pending_quantum_tasks.swap_remove(index))
+            task.last_update = now;
+            if let crate::task::TaskType::Classical(residual) = &mut task.task_type {
+                let num_ops = if dedicated || shares.is_some() {
+                    std::cmp::min(*residual, num_ops)
+                } else {
+                    num_ops
+                };
+                assert!(*residual >= num_ops);
+                *residual -= num_ops;
+                if *residual == 0 {
+                    finished_tasks_start_times.push((
+                        task.job_id,
+                        task.branch,
+                        task.start_time,
+                        task.resource,
+                    ));
+                    finished_task_job_ids.insert(task.job_id);
+                } else {
+                    residuals.push(*residual);
+                }
+            }
         }
-    }
 
-    fn log_internals(&self, hdr: &str, now: u64) {
-        log::debug!(
-            "{} {} active jobs [{}] {:?}",
-            hdr,
-            now,
-            self.active_jobs.len(),
-            self.active_jobs
-        );
-        log::debug!(
-            "{} {} classical tasks [{}] {:?}",
-            hdr,
+        // add a performance sample for the task duration
+        for (job_id, branch, start_time, resource) in finished_tasks_start_times {
+            self.trace(crate::trace::TraceRecord::TaskEnd {
+                time: now,
+                job_id,
+                branch,
+                quantum: false,
+                resource,
+                completed: true,
+            });
+            let job = self.active_jobs.get_mut(&job_id).unwrap();
+            job.classical_time += now - start_time;
+            if self.config.save_iteration_durations {
+                series.add("classical_dur", &job.label, to_seconds(now - start_time));
+            }
+        }
+
+        if !dedicated && !residuals.is_empty() {
+            // find the smallest residual, if there tasks that
+            // are still active after this event is fully handled
+            residuals.sort_unstable();
+            let smallest_residual = residuals.first().unwrap();
+
+            log::debug!(
+                "YYY {} + {} = {}",
+                now,
+                smallest_residual,
+                now + smallest_residual
+            );
+
+            // create an event that is handled when the task with
+            // the smallest residual finishes, unless there are new
+            // tasks arriving that will mess the schedule
+            events.push(Event::UpdateClassicalTasks(now + smallest_residual));
+        }
+
+        // remove the completed tasks from the active set
+        self.active_classical_tasks
+            .retain(|x| !finished_task_job_ids.contains(&x.job_id));
+        single.time_avg(
+            "active_classical_tasks",
             now,
-            self.active_classical_tasks.len(),
-            self.active_classical_tasks
+            self.active_classical_tasks.len() as f64,
         );
-        log::debug!(
-            "{} {} pending quantum tasks [{}] {:?}",
+
+        if dedicated {
+            // assign the free workers to the waiting tasks
+            while self
+                .active_classical_tasks
+                .iter()
+                .filter(|task| task.resource.is_some())
+                .count()
+                < self.num_workers
+            {
+                let Some(index) = self
+                    .classical_scheduler
+                    .next_waiting(&self.active_classical_tasks, &self.active_jobs)
+                else {
+                    break;
+                };
+                let worker_id = (0..)
+                    .find(|worker_id| {
+                        !self
+                            .active_classical_tasks
+                            .iter()
+                            .any(|task| task.resource == Some(*worker_id))
+                    })
+                    .unwrap();
+                self.active_classical_tasks[index].resource = Some(worker_id);
+                self.trace(crate::trace::TraceRecord::TaskStart {
+                    time: now,
+                    job_id: self.active_classical_tasks[index].job_id,
+                    branch: self.active_classical_tasks[index].branch,
+                    quantum: false,
+                    resource: Some(worker_id),
+                });
+            }
+            single.time_avg(
+                "waiting_classical_tasks",
+                now,
+                self.active_classical_tasks
+                    .iter()
+                    .filter(|task| task.resource.is_none())
+                    .count() as f64,
+            );
+
+            // schedule an update when the first task holding a worker finishes
+            if let Some(time) = self
+                .active_classical_tasks
+                .iter()
+                .filter(|task| task.resource.is_some())
+                .map(|task| match task.task_type {
+                    crate::task::TaskType::Classical(residual) => {
+                        (residual as f64 * 1e9_f64 / self.config.worker_capacity as f64).ceil()
+                            as u64
+                    }
+                    crate::task::TaskType::Quantum(_) => {
+                        panic!("quantum task among classical ones")
+                    }
+                })
+                .min()
+            {
+                events.push(Event::UpdateClassicalTasks(now + time));
+            }
+        }
+
+        // for all jobs that are still active, schedule the
+        // next task, otherwise remove the job from the active set
+        for job_id in &finished_task_job_ids {
+            if self.cold_start.is_some() {
+                self.active_jobs.get_mut(job_id).unwrap().warm_until =
+                    Some(now + to_nanoseconds(self.config.keep_alive));
+            }
+            let new_task_res = self.new_task_for_job(now, *job_id, false, series, single);
+            if new_task_res.0 {
+                let res = self.active_jobs.remove(job_id);
+                assert!(res.is_some());
+            } else {
+                for event in new_task_res.1 {
+                    events.push(event);
+                }
+            }
+        }
+        events
+    }
+
+    /// Change the number of serverless workers, after updating the classical
+    /// tasks with the previous capacity.
+    /// Return the events to be scheduled.
+    fn set_num_workers(
+        &mut self,
+        now: u64,
+        num_workers: usize,
+        series: &mut crate::output::OutputSeries,
+        single: &mut crate::output::OutputSingle,
+    ) -> Vec<Event> {
+        // the workers being added are counted against the maximum when
+        // scaling up, hence the bounds always hold
+        assert!((self.config.min_workers..=self.config.max_workers).contains(&num_workers));
+        let mut events = self.update_classical_tasks(now, series, single);
+        self.num_workers = num_workers;
+        single.time_avg("serverless_workers", now, num_workers as f64);
+        if self.classical_scheduler.is_dedicated() {
+            // assign the new workers, if any, to the waiting tasks
+            events.push(Event::UpdateClassicalTasks(now));
+        }
+        events
+    }
+
+    /// Return the indices of the pending quantum tasks that can be executed
+    /// on a given quantum computer.
+    fn eligible_quantum_tasks(&self, qpu_id: usize) -> Vec<usize> {
+        let free_qubits = self.free_qubits(qpu_id);
+        let fits = |task: &crate::task::Task| {
+            self.active_jobs.get(&task.job_id).unwrap().num_qubits <= free_qubits
+        };
+        let indices = |condition: &dyn Fn(&crate::task::Task) -> bool| {
+            self.pending_quantum_tasks
+                .iter()
+                .enumerate()
+                .filter_map(|(index, task)| if condition(task) { Some(index) } else { None })
+                .collect::<Vec<usize>>()
+        };
+        match self.quantum_queue_mode {
+            crate::quantum_computer::QueueMode::Central => indices(&fits),
+            crate::quantum_computer::QueueMode::PerQpu => {
+                indices(&|task| task.resource == Some(qpu_id) && fits(task))
+            }
+            crate::quantum_computer::QueueMode::PerQpuStealing => {
+                let own = indices(&|task| task.resource == Some(qpu_id) && fits(task));
+                if !own.is_empty() {
+                    return own;
+                }
+
+                // steal from the queue with most tasks that fit
+                let mut num_fitting = vec![0; self.quantum_computers.len()];
+                for task in &self.pending_quantum_tasks {
+                    if fits(task) {
+                        num_fitting[task.resource.unwrap()] += 1;
+                    }
+                }
+                let mut victim = None;
+                for (other_qpu_id, num) in num_fitting.iter().enumerate() {
+                    if *num > 0 && victim.is_none_or(|x| *num > num_fitting[x]) {
+                        victim = Some(other_qpu_id);
+                    }
+                }
+                match victim {
+                    Some(victim) => indices(&|task| task.resource == Some(victim) && fits(task)),
+                    None => vec![],
+                }
+            }
+        }
+    }
+
+    /// Select the next pending quantum task to be executed on a given
+    /// quantum computer, among those eligible.
+    fn schedule_next_quantum_task(&mut self, now: u64, qpu_id: usize) -> Option<crate::task::Task> {
+        let eligible = self.eligible_quantum_tasks(qpu_id);
+        if eligible.is_empty() {
+            None
+        } else if eligible.len() == self.pending_quantum_tasks.len() {
+            let index = self.quantum_scheduler.select(
+                &self.pending_quantum_tasks,
+                &self.active_jobs,
+                now,
+            )?;
+            Some(self.pending_quantum_tasks.swap_remove(index))
+        } else {
+            let candidates = eligible
+                .iter()
+                .map(|index| self.pending_quantum_tasks[*index].clone())
+                .collect::<Vec<crate::task::Task>>();
+            let index = self
+                .quantum_scheduler
+                .select(&candidates, &self.active_jobs, now)?;
+            Some(self.pending_quantum_tasks.swap_remove(eligible[index]))
+        }
+    }
+
+    /// Update the metrics about the pending quantum tasks.
+    fn update_pending_quantum_metrics(&self, now: u64, single: &mut crate::output::OutputSingle) {
+        single.time_avg(
+            "pending_quantum_tasks",
+            now,
+            self.pending_quantum_tasks.len() as f64,
+        );
+        if self.quantum_queue_mode != crate::quantum_computer::QueueMode::Central {
+            let mut queue_lens = vec![0; self.quantum_computers.len()];
+            for task in &self.pending_quantum_tasks {
+                queue_lens[task.resource.unwrap()] += 1;
+            }
+            for (qpu_id, queue_len) in queue_lens.iter().enumerate() {
+                single.time_avg(&qpu_queue_len_metric(qpu_id), now, *queue_len as f64);
+            }
+        }
+    }
+
+    /// Dispatch pending quantum tasks to the idle quantum computers, if any.
+    /// Return the events to be scheduled.
+    fn dispatch_quantum_tasks(
+        &mut self,
+        now: u64,
+        single: &mut crate::output::OutputSingle,
+    ) -> Vec<Event> {
+        let mut events = vec![];
+        for qpu_id in 0..self.quantum_computers.len() {
+            if self.pending_quantum_tasks.is_empty() {
+                break;
+            }
+            if let Some(session) = self.qpu_sessions[qpu_id] {
+                // a reserved quantum computer executes only the tasks of the
+                // job holding the session, which are pending if the job has
+                // more tasks in the same stage
+                if self.qpu_outages[qpu_id] == 0 && self.is_quantum_computer_idle(qpu_id) {
+                    if let Some(index) = self
+                        .pending_quantum_tasks
+                        .iter()
+                        .position(|task| task.job_id == session.job_id)
+                    {
+                        let new_task = self.pending_quantum_tasks.swap_remove(index);
+                        self.update_pending_quantum_metrics(now, single);
+                        events.append(&mut self.start_quantum_task(now, qpu_id, new_task, single));
+                    }
+                }
+            }
+        }
+
+        // the other quantum computers that can execute some of the pending
+        // tasks are served in the order given by the selection policy
+        let mut candidates = (0..self.quantum_computers.len()).collect::<Vec<usize>>();
+        while !self.pending_quantum_tasks.is_empty() {
+            candidates.retain(|qpu_id| {
+                self.is_quantum_computer_available(*qpu_id)
+                    && !self.eligible_quantum_tasks(*qpu_id).is_empty()
+            });
+            let Some(qpu_id) = self.qpu_selection_policy.select(
+                &candidates,
+                &self.quantum_computers,
+                &self.qpu_loads(),
+                &mut self.qpu_selection_rng,
+            ) else {
+                break;
+            };
+            candidates.retain(|x| *x != qpu_id);
+            if let Some(batch_policy) = self.batch_policy {
+                // wait until the batch is full or the oldest task has
+                // been waiting for too long
+                let eligible = self.eligible_quantum_tasks(qpu_id);
+                let oldest = eligible
+                    .iter()
+                    .map(|index| self.pending_quantum_tasks[*index].start_time)
+                    .min()
+                    .unwrap();
+                if eligible.len() < batch_policy.max_size && now < oldest + batch_policy.max_wait {
+                    events.push(Event::BatchTimeout(oldest + batch_policy.max_wait));
+                    continue;
+                }
+                let mut tasks = vec![];
+                while tasks.len() < batch_policy.max_size {
+                    match self.schedule_next_quantum_task(now, qpu_id) {
+                        Some(new_task) => tasks.push(new_task),
+                        None => break,
+                    }
+                }
+                if !tasks.is_empty() {
+                    self.update_pending_quantum_metrics(now, single);
+                    events.append(&mut self.start_quantum_batch(now, qpu_id, tasks, single));
+                }
+                continue;
+            }
+            // with multi-programming, start tasks as long as they fit
+            while let Some(new_task) = self.schedule_next_quantum_task(now, qpu_id) {
+                self.update_pending_quantum_metrics(now, single);
+                self.open_session(now, qpu_id, new_task.job_id, single);
+                events.append(&mut self.start_quantum_task(now, qpu_id, new_task, single));
+                if !self.is_quantum_computer_available(qpu_id) {
+                    break;
+                }
+            }
+        }
+        events
+    }
+
+    /// Return the number of quantum tasks in execution on each quantum
+    /// computer or waiting in its queue, if any.
+    fn qpu_loads(&self) -> Vec<usize> {
+        let mut loads = vec![0; self.quantum_computers.len()];
+        for task in self
+            .pending_quantum_tasks
+            .iter()
+            .chain(self.active_quantum_tasks.iter())
+        {
+            if let Some(qpu_id) = task.resource {
+                loads[qpu_id] += 1;
+            }
+        }
+        loads
+    }
+
+    /// Return true if the given quantum computer is not executing any task.
+    fn is_quantum_computer_idle(&self, qpu_id: usize) -> bool {
+        !self
+            .active_quantum_tasks
+            .iter()
+            .any(|task| task.resource == Some(qpu_id))
+    }
+
+    /// Return true if the given quantum computer is online, not reserved,
+    /// and it is idle or, with multi-programming, has some qubits left.
+    fn is_quantum_computer_available(&self, qpu_id: usize) -> bool {
+        self.qpu_outages[qpu_id] == 0
+            && self.qpu_sessions[qpu_id].is_none()
+            && (self.is_quantum_computer_idle(qpu_id)
+                || (self.multi_programming_policy.is_some() && self.free_qubits(qpu_id) > 0))
+    }
+
+    /// Return the maximum number of qubits of a circuit that can be started
+    /// now on the given quantum computer, assuming it is available.
+    fn free_qubits(&self, qpu_id: usize) -> u16 {
+        if self.multi_programming_policy.is_none() {
+            return self.quantum_computers[qpu_id].max_qubits;
+        }
+        self.active_quantum_tasks
+            .iter()
+            .filter(|task| task.resource == Some(qpu_id))
+            .fold(self.quantum_computers[qpu_id].max_qubits, |free, task| {
+                free.saturating_sub(self.active_jobs.get(&task.job_id).unwrap().num_qubits)
+            })
+    }
+
+    /// Return the quantum computer reserved for a job, if any.
+    fn session_of(&self, job_id: u64) -> Option<usize> {
+        self.qpu_sessions
+            .iter()
+            .position(|session| session.is_some_and(|session| session.job_id == job_id))
+    }
+
+    /// Take a quantum computer offline, which releases its reservation, if any.
+    /// Downtimes and failures may overlap: a quantum computer is back online
+    /// only when all of them are over.
+    fn quantum_computer_offline(
+        &mut self,
+        now: u64,
+        qpu_id: usize,
+        single: &mut crate::output::OutputSingle,
+    ) {
+        self.qpu_outages[qpu_id] += 1;
+        single.time_avg(&qpu_availability_metric(qpu_id), now, 0.0);
+        if self.qpu_sessions[qpu_id].is_some() {
+            self.close_session(now, qpu_id, single);
+        }
+    }
+
+    /// Bring a quantum computer back online, if there are no other outages.
+    /// Return the events to be scheduled.
+    fn quantum_computer_online(
+        &mut self,
+        now: u64,
+        qpu_id: usize,
+        single: &mut crate::output::OutputSingle,
+    ) -> Vec<Event> {
+        assert!(self.qpu_outages[qpu_id] > 0);
+        self.qpu_outages[qpu_id] -= 1;
+        if self.qpu_outages[qpu_id] > 0 {
+            return vec![];
+        }
+        single.time_avg(&qpu_availability_metric(qpu_id), now, 1.0);
+        self.dispatch_quantum_tasks(now, single)
+    }
+
+    /// Remove the quantum tasks executing on a given quantum computer.
+    /// Return the tasks interrupted.
+    fn interrupt_quantum_tasks(
+        &mut self,
+        now: u64,
+        qpu_id: usize,
+        single: &mut crate::output::OutputSingle,
+    ) -> Vec<crate::task::Task> {
+        let (interrupted, active): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.active_quantum_tasks)
+                .into_iter()
+                .partition(|task| task.resource == Some(qpu_id));
+        self.active_quantum_tasks = active;
+        for task in &interrupted {
+            self.trace(crate::trace::TraceRecord::TaskEnd {
+                time: now,
+                job_id: task.job_id,
+                branch: task.branch,
+                quantum: true,
+                resource: Some(qpu_id),
+                completed: false,
+            });
+        }
+        single.time_avg(
+            "active_quantum_tasks",
+            now,
+            self.active_quantum_tasks.len() as f64,
+        );
+        self.update_busy_time(now, qpu_id, single);
+        interrupted
+    }
+
+    /// Remove a job that has been aborted or has failed, with its other
+    /// tasks, if any.
+    /// Return: the time lost executing the other tasks of the job, which
+    /// have been interrupted, in ns; the new events to be scheduled.
+    fn remove_job(
+        &mut self,
+        now: u64,
+        job_id: u64,
+        single: &mut crate::output::OutputSingle,
+    ) -> (u64, Vec<Event>) {
+        let res = self.active_jobs.remove(&job_id);
+        assert!(res.is_some());
+        if let Some(qpu_id) = self.session_of(job_id) {
+            self.close_session(now, qpu_id, single);
+        }
+        self.transferring_tasks.retain(|task| task.job_id != job_id);
+        let num_pending = self.pending_quantum_tasks.len();
+        self.pending_quantum_tasks
+            .retain(|task| task.job_id != job_id);
+        if self.pending_quantum_tasks.len() != num_pending {
+            self.update_pending_quantum_metrics(now, single);
+        }
+
+        let (interrupted, active): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.active_quantum_tasks)
+                .into_iter()
+                .partition(|task| task.job_id == job_id);
+        self.active_quantum_tasks = active;
+        if interrupted.is_empty() {
+            return (0, vec![]);
+        }
+        let mut lost_quantum_time = 0;
+        for task in &interrupted {
+            self.trace(crate::trace::TraceRecord::TaskEnd {
+                time: now,
+                job_id,
+                branch: task.branch,
+                quantum: true,
+                resource: task.resource,
+                completed: false,
+            });
+            lost_quantum_time += now - task.last_update;
+        }
+        single.time_avg(
+            "active_quantum_tasks",
+            now,
+            self.active_quantum_tasks.len() as f64,
+        );
+        let mut num_interrupted = std::collections::BTreeMap::new();
+        for task in &interrupted {
+            *num_interrupted.entry(task.resource.unwrap()).or_insert(0) += 1;
+        }
+        let mut events = vec![];
+        for (qpu_id, num_interrupted) in num_interrupted {
+            self.update_busy_time(now, qpu_id, single);
+            let num_left = self
+                .active_quantum_tasks
+                .iter()
+                .filter(|task| task.resource == Some(qpu_id))
+                .count();
+            if num_left > 0 {
+                events.append(&mut self.share_quantum_computer(
+                    now,
+                    qpu_id,
+                    num_left + num_interrupted - 1,
+                    num_left - 1,
+                ));
+            }
+        }
+        events.append(&mut self.dispatch_quantum_tasks(now, single));
+        (lost_quantum_time, events)
+    }
+
+    /// Schedule again an interrupted quantum task, which executes again the
+    /// whole quantum iteration.
+    /// Return the events to be scheduled.
+    fn requeue_quantum_task(
+        &mut self,
+        now: u64,
+        mut task: crate::task::Task,
+        single: &mut crate::output::OutputSingle,
+    ) -> Vec<Event> {
+        task.task_type = crate::task::TaskType::Quantum(
+            self.active_jobs
+                .get(&task.job_id)
+                .unwrap()
+                .quantum_iteration_duration(task.branch),
+        );
+        task.resource = None;
+        self.manage_task(now, task, single)
+    }
+
+    /// Account for the busy time of a quantum computer if it has become idle.
+    fn update_busy_time(
+        &mut self,
+        now: u64,
+        qpu_id: usize,
+        single: &mut crate::output::OutputSingle,
+    ) {
+        if self.is_quantum_computer_idle(qpu_id) {
+            if let Some(busy_since) = self.qpu_busy_since[qpu_id].take() {
+                self.qpu_busy_times[qpu_id] += now - busy_since;
+                single.time_avg(&qpu_utilization_metric(qpu_id), now, 0.0);
+            }
+        }
+    }
+
+    /// Reserve a quantum computer for a job, if its priority requires so
+    /// and it does not hold a reservation already.
+    fn open_session(
+        &mut self,
+        now: u64,
+        qpu_id: usize,
+        job_id: u64,
+        single: &mut crate::output::OutputSingle,
+    ) {
+        let priority = self.active_jobs.get(&job_id).unwrap().priority;
+        if self.sessions.contains_key(&priority) && self.session_of(job_id).is_none() {
+            assert!(self.qpu_sessions[qpu_id].is_none());
+            self.qpu_sessions[qpu_id] = Some(Session {
+                job_id,
+                session_id: self.next_session_id,
+                idle_since: now,
+            });
+            self.next_session_id += 1;
+            self.update_session_metrics(now, single);
+        }
+    }
+
+    /// Return the idle timeout of the session of a job.
+    fn idle_timeout_of(&self, job_id: u64) -> u64 {
+        let priority = self.active_jobs.get(&job_id).unwrap().priority;
+        self.sessions.get(&priority).unwrap().idle_timeout.unwrap()
+    }
+
+    /// Release the reservation of a quantum computer.
+    fn close_session(&mut self, now: u64, qpu_id: usize, single: &mut crate::output::OutputSingle) {
+        assert!(self.qpu_sessions[qpu_id].is_some());
+        self.qpu_sessions[qpu_id] = None;
+        self.update_session_metrics(now, single);
+    }
+
+    /// Update the metrics about the sessions.
+    fn update_session_metrics(&self, now: u64, single: &mut crate::output::OutputSingle) {
+        single.time_avg(
+            "reserved_quantum_computers",
+            now,
+            self.qpu_sessions.iter().filter(|x| x.is_some()).count() as f64,
+        );
+    }
+
+    /// Start the execution of a quantum task on a given quantum computer.
+    /// Return the events of the end of the quantum iteration and, with
+    /// multi-programming, of the new ends of the other tasks executing on
+    /// the same quantum computer.
+    fn start_quantum_task(
+        &mut self,
+        now: u64,
+        qpu_id: usize,
+        new_task: crate::task::Task,
+        single: &mut crate::output::OutputSingle,
+    ) -> Vec<Event> {
+        let mut events = vec![];
+        let mut duration = self.quantum_computers[qpu_id].duration(new_task.quantum_duration());
+        if let Some(multi_programming_policy) = self.multi_programming_policy {
+            let num_others = self
+                .active_quantum_tasks
+                .iter()
+                .filter(|task| task.resource == Some(qpu_id))
+                .count();
+            duration = multi_programming_policy.duration(duration, num_others);
+            single.sample_avg("multi_programming_level", (num_others + 1) as f64);
+            single.sample_avg("fidelity", multi_programming_policy.fidelity(num_others));
+            if num_others > 0 {
+                events = self.share_quantum_computer(now, qpu_id, num_others - 1, num_others);
+            }
+        }
+        events.push(self.execute_quantum_task(now, qpu_id, new_task, duration, single));
+        events
+    }
+
+    /// With multi-programming, update the durations of the quantum tasks
+    /// executing on a given quantum computer after another task has started
+    /// or ended there, since they all slow down with the number of circuits
+    /// sharing the quantum computer.
+    /// Return the events of the new ends of the tasks, which make obsolete
+    /// the previous ones.
+    /// Parameters:
+    /// - `now`: the current time, in ns
+    /// - `qpu_id`: the quantum computer identifier
+    /// - `num_others_before`: the number of other circuits that each task
+    ///   was sharing the quantum computer with until now
+    /// - `num_others_after`: the number of other circuits that each task
+    ///   shares the quantum computer with from now on
+    fn share_quantum_computer(
+        &mut self,
+        now: u64,
+        qpu_id: usize,
+        num_others_before: usize,
+        num_others_after: usize,
+    ) -> Vec<Event> {
+        let Some(multi_programming_policy) = self.multi_programming_policy else {
+            return vec![];
+        };
+        let mut events = vec![];
+        for task in &mut self.active_quantum_tasks {
+            if task.resource != Some(qpu_id) {
+                continue;
+            }
+            // the duration of a task in execution is counted from the time
+            // when it started
+            if let crate::task::TaskType::Quantum(duration) = &mut task.task_type {
+                let elapsed = now - task.last_update;
+                *duration = elapsed
+                    + multi_programming_policy.rescale(
+                        *duration - elapsed,
+                        num_others_before,
+                        num_others_after,
+                    );
+                events.push(Event::QuantumIterationEnd(task.last_update + *duration));
+            }
+        }
+        events
+    }
+
+    /// Execute a quantum task on a given quantum computer for a given time.
+    /// Return the event of the end of the quantum iteration.
+    fn execute_quantum_task(
+        &mut self,
+        now: u64,
+        qpu_id: usize,
+        mut new_task: crate::task::Task,
+        duration: u64,
+        single: &mut crate::output::OutputSingle,
+    ) -> Event {
+        // a task never starts on a quantum computer that is offline or
+        // without enough free qubits and, without stealing, never leaves the
+        // queue to which it was routed
+        assert_eq!(0, self.qpu_outages[qpu_id]);
+        assert!(
+            self.free_qubits(qpu_id) >= self.active_jobs.get(&new_task.job_id).unwrap().num_qubits
+        );
+        assert!(
+            self.quantum_queue_mode != crate::quantum_computer::QueueMode::PerQpu
+                || new_task.resource.is_none_or(|x| x == qpu_id)
+        );
+
+        new_task.last_update = now;
+        new_task.resource = Some(qpu_id);
+        new_task.task_type = crate::task::TaskType::Quantum(duration);
+        let event = Event::QuantumIterationEnd(now + duration);
+        self.trace(crate::trace::TraceRecord::TaskStart {
+            time: now,
+            job_id: new_task.job_id,
+            branch: new_task.branch,
+            quantum: true,
+            resource: Some(qpu_id),
+        });
+        self.active_quantum_tasks.push(new_task);
+        single.time_avg(
+            "active_quantum_tasks",
+            now,
+            self.active_quantum_tasks.len() as f64,
+        );
+        if self.qpu_busy_since[qpu_id].is_none() {
+            self.qpu_busy_since[qpu_id] = Some(now);
+            single.time_avg(&qpu_utilization_metric(qpu_id), now, 1.0);
+        }
+        event
+    }
+
+    /// Start the execution of a batch of quantum tasks on a given quantum
+    /// computer, which all end at the same time.
+    /// Return the events of the end of the quantum iterations.
+    fn start_quantum_batch(
+        &mut self,
+        now: u64,
+        qpu_id: usize,
+        tasks: Vec<crate::task::Task>,
+        single: &mut crate::output::OutputSingle,
+    ) -> Vec<Event> {
+        let durations = tasks
+            .iter()
+            .map(|task| self.quantum_computers[qpu_id].duration(task.quantum_duration()))
+            .collect::<Vec<u64>>();
+        let batch_duration = self.batch_policy.unwrap().duration(&durations);
+        single.sample_avg("batch_size", tasks.len() as f64);
+        tasks
+            .into_iter()
+            .map(|task| self.execute_quantum_task(now, qpu_id, task, batch_duration, single))
+            .collect()
+    }
+
+    /// Add a record to the trace, if enabled.
+    fn trace(&mut self, record: crate::trace::TraceRecord) {
+        if let Some(trace) = &mut self.trace {
+            if let Err(err) = trace.add(record) {
+                log::error!("cannot write the trace, which is disabled: {}", err);
+                self.trace = None;
+            }
+        }
+    }
+
+    fn log_internals(&self, hdr: &str, now: u64) {
+        log::debug!(
+            "{} {} active jobs [{}] {:?}",
+            hdr,
+            now,
+            self.active_jobs.len(),
+            self.active_jobs
+        );
+        log::debug!(
+            "{} {} classical tasks [{}] {:?}",
+            hdr,
+            now,
+            self.active_classical_tasks.len(),
+            self.active_classical_tasks
+        );
+        log::debug!(
+            "{} {} pending quantum tasks [{}] {:?}",
             hdr,
             now,
             self.pending_quantum_tasks.len(),
@@ -606,58 +2058,212 @@ impl Simulation {
     }
 
     /// Return: boolean that is true if the job has to be removed, false otherwise;
-    /// a new event to be scheduled.
+    /// the new events to be scheduled.
     fn new_task_for_job(
         &mut self,
         now: u64,
         job_id: u64,
+        after_quantum: bool,
         series: &mut crate::output::OutputSeries,
         single: &mut crate::output::OutputSingle,
-    ) -> (bool, Option<Event>) {
+    ) -> (bool, Vec<Event>) {
         let job = self.active_jobs.get_mut(&job_id).unwrap();
-        if let Some(new_task) = job.next_task(now) {
-            (false, self.manage_task(now, new_task, single))
+        if let Some(new_tasks) = job.next_tasks(now) {
+            let mut events = vec![];
+            for new_task in new_tasks {
+                // the task reaches its stage only after the hand-off between
+                // the classical and quantum stages, if any: between two
+                // quantum stages, the results of the previous one are
+                // retrieved before the new tasks are submitted
+                let before_quantum =
+                    matches!(new_task.task_type, crate::task::TaskType::Quantum(_));
+                if let Some(network) = &self.network {
+                    if before_quantum || after_quantum {
+                        let mut delay = 0;
+                        if after_quantum {
+                            delay += network.retrieval_delay(&mut self.network_rng);
+                        }
+                        if before_quantum {
+                            delay += network.submission_delay(&mut self.network_rng);
+                        }
+                        // the submissions, and the retrievals preceding
+                        // them, are accounted for when the quantum stage is
+                        // complete
+                        if !before_quantum {
+                            self.active_jobs.get_mut(&job_id).unwrap().network_time += delay;
+                        }
+                        events.push(Event::HandOffEnd(now + delay, job_id, new_task.branch));
+                        self.transferring_tasks.push(new_task);
+                        continue;
+                    }
+                }
+                events.append(&mut self.manage_task(now, new_task, single));
+            }
+            (false, events)
         } else {
             series.add("job_time", &job.label, to_seconds(now - job.time_arrival));
-            (true, None)
+            let classical_service_time = job.classical_service_time(self.config.worker_capacity);
+            series.add(
+                "classical_service_time",
+                &job.label,
+                to_seconds(classical_service_time),
+            );
+            series.add(
+                "classical_waiting_time",
+                &job.label,
+                to_seconds(job.classical_time.saturating_sub(classical_service_time)),
+            );
+            series.add(
+                "quantum_queueing_time",
+                &job.label,
+                to_seconds(job.quantum_time - job.quantum_service_time),
+            );
+            series.add(
+                "quantum_service_time",
+                &job.label,
+                to_seconds(job.quantum_service_time),
+            );
+            if self.network.is_some() {
+                series.add("network_time", &job.label, to_seconds(job.network_time));
+            }
+            if self.config.save_jobs && now >= to_nanoseconds(self.config.warmup_period) {
+                self.jobs.push(job.to_csv(now));
+            }
+            if let Some(deadline) = job.deadline {
+                series.add(
+                    "lateness",
+                    &job.label,
+                    to_seconds(now) - to_seconds(deadline),
+                );
+                single.ratio(&deadline_miss_metric(&job.label), now > deadline);
+            }
+            (true, vec![])
         }
     }
 
+    /// Add a task to those sharing the serverless workers.
+    fn add_classical_task(
+        &mut self,
+        now: u64,
+        new_task: crate::task::Task,
+        single: &mut crate::output::OutputSingle,
+    ) {
+        if !self.classical_scheduler.is_dedicated() {
+            self.trace(crate::trace::TraceRecord::TaskStart {
+                time: now,
+                job_id: new_task.job_id,
+                branch: new_task.branch,
+                quantum: false,
+                resource: None,
+            });
+        }
+        self.active_classical_tasks.push(new_task);
+        single.time_avg(
+            "active_classical_tasks",
+            now,
+            self.active_classical_tasks.len() as f64,
+        );
+    }
+
     fn manage_task(
         &mut self,
         now: u64,
         new_task: crate::task::Task,
         single: &mut crate::output::OutputSingle,
-    ) -> Option<Event> {
+    ) -> Vec<Event> {
         match &new_task.task_type {
             crate::task::TaskType::Classical(_residual) => {
-                let event = Some(Event::UpdateClassicalTasks(now));
-                self.active_classical_tasks.push(new_task);
-                single.time_avg(
-                    "active_classical_tasks",
-                    now,
-                    self.active_classical_tasks.len() as f64,
+                // the task waits for a new function instance to start,
+                // unless the job's one is still warm
+                if let Some(cold_start) = self.cold_start {
+                    let job = self.active_jobs.get(&new_task.job_id).unwrap();
+                    let cold = job.warm_until.is_none_or(|warm_until| warm_until < now);
+                    single.ratio(&cold_start_metric(&job.label), cold);
+                    if cold {
+                        let event = Event::ColdStartEnd(
+                            now + cold_start.sample(&mut self.cold_start_rng),
+                            new_task.job_id,
+                        );
+                        self.starting_classical_tasks.push(new_task);
+                        return vec![event];
+                    }
+                }
+                self.add_classical_task(now, new_task, single);
+                vec![Event::UpdateClassicalTasks(now)]
+            }
+            crate::task::TaskType::Quantum(_duration) => {
+                // the job holds a session: execute the task immediately,
+                // unless the quantum computer is busy with another task of
+                // the same stage
+                if let Some(qpu_id) = self.session_of(new_task.job_id) {
+                    if self.is_quantum_computer_idle(qpu_id) {
+                        return self.start_quantum_task(now, qpu_id, new_task, single);
+                    }
+                }
+
+                let num_qubits = self.active_jobs.get(&new_task.job_id).unwrap().num_qubits;
+                let fitting = (0..self.quantum_computers.len())
+                    .filter(|qpu_id| self.quantum_computers[*qpu_id].fits(num_qubits))
+                    .collect::<Vec<usize>>();
+                let candidates =
+                    if self.quantum_queue_mode == crate::quantum_computer::QueueMode::Central {
+                        // only the available quantum computers are candidates
+                        fitting
+                            .into_iter()
+                            .filter(|qpu_id| {
+                                self.is_quantum_computer_available(*qpu_id)
+                                    && num_qubits <= self.free_qubits(*qpu_id)
+                            })
+                            .collect::<Vec<usize>>()
+                    } else {
+                        // avoid the reserved and offline quantum computers,
+                        // if possible
+                        let usable = |qpu_id: &usize| {
+                            self.qpu_sessions[*qpu_id].is_none() && self.qpu_outages[*qpu_id] == 0
+                        };
+                        let fitting = if fitting.iter().any(usable) {
+                            fitting.into_iter().filter(usable).collect::<Vec<usize>>()
+                        } else {
+                            fitting
+                        };
+
+                        // route the task to the shortest queues, counting
+                        // also the task in execution, if any
+                        let loads = self.qpu_loads();
+                        let min_load = fitting.iter().map(|x| loads[*x]).min().unwrap();
+                        fitting
+                            .into_iter()
+                            .filter(|qpu_id| loads[*qpu_id] == min_load)
+                            .collect::<Vec<usize>>()
+                    };
+                let qpu_id = self.qpu_selection_policy.select(
+                    &candidates,
+                    &self.quantum_computers,
+                    &self.qpu_loads(),
+                    &mut self.qpu_selection_rng,
                 );
-                event
-            }
-            crate::task::TaskType::Quantum(duration) => {
-                if self.active_quantum_tasks.len() < self.config.num_quantum_computers {
-                    let event = Some(Event::QuantumIterationEnd(now + *duration));
-                    self.active_quantum_tasks.push(new_task);
-                    single.time_avg(
-                        "active_quantum_tasks",
-                        now,
-                        self.active_quantum_tasks.len() as f64,
-                    );
-                    event
-                } else {
-                    self.pending_quantum_tasks.push(new_task);
-                    single.time_avg(
-                        "pending_quantum_tasks",
-                        now,
-                        self.pending_quantum_tasks.len() as f64,
-                    );
-                    None
+                match qpu_id {
+                    Some(qpu_id)
+                        if self.batch_policy.is_none()
+                            && self.is_quantum_computer_available(qpu_id)
+                            && num_qubits <= self.free_qubits(qpu_id) =>
+                    {
+                        self.open_session(now, qpu_id, new_task.job_id, single);
+                        self.start_quantum_task(now, qpu_id, new_task, single)
+                    }
+                    _ => {
+                        let mut new_task = new_task;
+                        if self.quantum_queue_mode != crate::quantum_computer::QueueMode::Central {
+                            new_task.resource = qpu_id;
+                        }
+                        self.pending_quantum_tasks.push(new_task);
+                        self.update_pending_quantum_metrics(now, single);
+                        if self.batch_policy.is_some() {
+                            self.dispatch_quantum_tasks(now, single)
+                        } else {
+                            vec![]
+                        }
+                    }
                 }
             }
         }
@@ -666,8 +2272,512 @@ impl Simulation {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    /// Return the configuration of a simulation where only one job arrives,
+    /// at time 0, with 4 qubits and priority 1, and every other feature is
+    /// disabled.
+    fn test_config() -> Config {
+        Config {
+            seed: 42,
+            duration: 1000.0,
+            job_interarrival: 1e9,
+            warmup_period: 0.0,
+            worker_capacity: 1_000_000_000,
+            num_serverless_workers: 1,
+            num_quantum_computers: 1,
+            quantum_computers: String::from(""),
+            qpu_selection_policy: String::from("first-fit"),
+            quantum_queue_mode: String::from("central"),
+            sessions: String::from(""),
+            batch: String::from(""),
+            multi_programming: String::from(""),
+            downtimes: String::from(""),
+            downtime_policy: String::from("finish"),
+            failures: String::from(""),
+            retry_budget: 3,
+            cold_start: String::from(""),
+            keep_alive: 60.0,
+            autoscaler: String::from(""),
+            min_workers: 1,
+            max_workers: 100,
+            scale_up_delay: 0.0,
+            autoscaler_interval: 2.0,
+            classical_scheduler: String::from("processor-sharing"),
+            submission_latency: String::from(""),
+            retrieval_latency: String::from(""),
+            payload: String::from(""),
+            bandwidth: 100.0,
+            max_classical_tasks: 50,
+            max_quantum_tasks: 50,
+            quantum_schedule_policy: String::from("fifo"),
+            quantum_tasks: String::from("1"),
+            convergence: String::from("trace"),
+            hide_job_sizes: false,
+            iteration_variability: String::from("fixed"),
+            job_type: String::from("VQE;4"),
+            priorities: String::from("1"),
+            deadlines: String::from(""),
+            save_iteration_durations: false,
+            save_jobs: false,
+            trace: None,
+            target_dur_qc_avg: std::collections::BTreeMap::new(),
+        }
+    }
+
+    /// Return the configuration of a simulation where jobs with 4 qubits
+    /// and priority 1 arrive on average every 2 s, with two quantum computers
+    /// and enough serverless workers to make the former the bottleneck.
+    fn stream_config() -> Config {
+        let mut config = test_config();
+        config.job_interarrival = 2.0;
+        config.num_serverless_workers = 10;
+        config.num_quantum_computers = 2;
+        config
+    }
+
+    /// Return a factory of jobs with 3 iterations, each with a quantum task
+    /// of 1 s, to be used with `stream_config()`.
+    fn stream_job_factory() -> crate::job::JobFactory {
+        crate::job::JobFactory::for_testing(1.0, 0.1, 1.0, 1.0, 3)
+    }
+
+    /// Run a simulation whose jobs are created by a given factory.
+    fn run(
+        config: Config,
+        job_factory: crate::job::JobFactory,
+    ) -> anyhow::Result<crate::output::Output> {
+        Ok(Simulation::with_job_factory(config, job_factory)?.run())
+    }
+
+    /// Run a simulation whose jobs with 4 qubits execute the workflow `test`,
+    /// with a given description.
+    fn run_workflow(mut config: Config, workflow: &str) -> anyhow::Result<crate::output::Output> {
+        config.job_type = String::from("WORKFLOW:test;4");
+        run(
+            config,
+            crate::job::JobFactory::for_testing_workflow("test", workflow)?,
+        )
+    }
+
+    /// Return the value of a single metric of a simulation, if any.
+    fn metric(output: &crate::output::Output, name: &str) -> Option<f64> {
+        let header = output.single.header();
+        let values = output.single.to_csv();
+        header
+            .split(',')
+            .zip(values.split(','))
+            .find(|(x, _)| *x == name)
+            .map(|(_, value)| value.parse().unwrap())
+    }
+
+    /// Return the values of a series of a simulation for the VQE jobs
+    /// with 4 qubits and priority 1, if any.
+    fn series(output: &crate::output::Output, name: &str) -> Vec<f64> {
+        output.series.series[name]
+            .values
+            .get("VQE,4,1")
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Return the values of a series of a simulation for the jobs of the
+    /// workflow `test` with 4 qubits and priority 1, if any.
+    fn workflow_series(output: &crate::output::Output, name: &str) -> Vec<f64> {
+        output.series.series[name]
+            .values
+            .get("WORKFLOW:test,4,1")
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Return the times, in s, at which the quantum tasks completed, from
+    /// the trace of a simulation in JSON Lines.
+    fn quantum_task_ends(trace: &crate::trace::TestBuffer) -> Vec<f64> {
+        trace
+            .lines()
+            .iter()
+            .filter(|x| x.contains("\"type\":\"task_end\"") && x.contains("\"stage\":\"quantum\""))
+            .map(|x| {
+                let time = x
+                    .split("\"time\":")
+                    .nth(1)
+                    .unwrap()
+                    .split(',')
+                    .next()
+                    .unwrap();
+                to_seconds(time.parse().unwrap())
+            })
+            .collect()
+    }
+
+    /// Return the average of some values.
+    fn average(values: &[f64]) -> f64 {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+
     #[test]
     fn test_simulation_run() -> anyhow::Result<()> {
+        let job_factory = crate::job::JobFactory::for_testing(1.0, 0.5, 2.0, 1.0, 3);
+        let output = run(test_config(), job_factory)?;
+        assert_eq!(Some(1.0), metric(&output, "num_job_accepted"));
+        assert_eq!(vec![7.5], series(&output, "job_time"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_workflow() -> anyhow::Result<()> {
+        // the stages of a job are executed one after the other
+        let trace = crate::trace::TestBuffer::default();
+        let mut config = test_config();
+        config.trace = Some(trace.trace_writer(crate::trace::TraceFormat::JsonLines));
+        let output = run_workflow(config, "classical 1\nquantum 2\nclassical 3")?;
+        assert_eq!(vec![6.0], workflow_series(&output, "job_time"));
+        assert_eq!(vec![3.0], quantum_task_ends(&trace));
+
+        // the quantum tasks of a stage are executed in parallel, if possible,
+        // and the next stage starts when all of them have completed
+        let trace = crate::trace::TestBuffer::default();
+        let mut config = test_config();
+        config.trace = Some(trace.trace_writer(crate::trace::TraceFormat::JsonLines));
+        config.num_quantum_computers = 2;
+        let output = run_workflow(config, "classical 1\nquantum 2 3\nclassical 1")?;
+        assert_eq!(vec![6.0], workflow_series(&output, "job_time"));
+        assert_eq!(vec![3.0, 3.0, 5.0], quantum_task_ends(&trace));
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_quantum_queue_mode() -> anyhow::Result<()> {
+        let mut job_times = vec![];
+        for mode in ["central", "per-qpu", "per-qpu-stealing"] {
+            let mut config = stream_config();
+            config.quantum_computers = String::from("fast:8:2;slow:8:0.5");
+            config.quantum_queue_mode = mode.to_string();
+            let output = run(config, stream_job_factory())?;
+
+            // the per-QPU queues, if any, hold all the pending tasks
+            let queue_lens = (0..2)
+                .filter_map(|qpu_id| metric(&output, &qpu_queue_len_metric(qpu_id)))
+                .collect::<Vec<f64>>();
+            let pending = metric(&output, "pending_quantum_tasks").unwrap();
+            if mode == "central" {
+                assert!(queue_lens.is_empty());
+            } else {
+                assert_eq!(2, queue_lens.len());
+                assert!((pending - queue_lens.iter().sum::<f64>()).abs() < 1e-9);
+            }
+            assert!(pending > 0.0);
+            job_times.push(average(&series(&output, "job_time")));
+        }
+
+        // idle quantum computers stealing tasks from the other queues
+        // shorten the jobs with respect to isolated queues
+        assert!(job_times[2] < job_times[1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_qpu_selection() -> anyhow::Result<()> {
+        // the quantum computer is chosen by the selection policy both when
+        // the task starts immediately and when it is dispatched from the
+        // queue, which is always the case with batches
+        for batch in ["", "1:0:0:1"] {
+            for (policy, expected) in [("first-fit", 3.5), ("fastest", 2.0)] {
+                let mut config = test_config();
+                config.quantum_computers = String::from("slow:8:0.5;fast:8:2");
+                config.qpu_selection_policy = policy.to_string();
+                config.batch = batch.to_string();
+                let job_factory = crate::job::JobFactory::for_testing(0.5, 0.5, 0.5, 1.0, 1);
+                let output = run(config, job_factory)?;
+                assert_eq!(vec![expected], series(&output, "job_time"), "{}", policy);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_batch() -> anyhow::Result<()> {
+        // a lone task waits for the batch to be filled for the maximum time,
+        // then the batch lasts 2 s plus half the duration of the circuit
+        let mut config = test_config();
+        config.batch = String::from("4:10:2:0.5");
+        let job_factory = crate::job::JobFactory::for_testing(1.0, 1.0, 1.0, 1.0, 1);
+        let output = run(config, job_factory)?;
+        assert_eq!(vec![15.5], series(&output, "job_time"));
+        assert_eq!(Some(1.0), metric(&output, "batch_size"));
+
+        // batches of one circuit, without waiting and overhead, are the same
+        // as executing the tasks individually
+        let mut job_times = vec![];
+        for batch in ["", "1:0:0:1"] {
+            let mut config = stream_config();
+            config.batch = batch.to_string();
+            let output = run(config, stream_job_factory())?;
+            job_times.push(series(&output, "job_time"));
+        }
+        assert_eq!(job_times[0], job_times[1]);
+
+        // with a heavy load, the batches gather circuits from different
+        // jobs, but never more than the maximum
+        let mut config = stream_config();
+        config.job_interarrival = 1.0;
+        config.batch = String::from("4:1:0.1:0.25");
+        let output = run(config, stream_job_factory())?;
+        let batch_size = metric(&output, "batch_size").unwrap();
+        assert!(batch_size > 1.0 && batch_size <= 4.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_job_time_breakdown() -> anyhow::Result<()> {
+        // the two quantum tasks are executed one after the other, hence the
+        // stage lasts from 1 s until 5.5 s, after a submission of 0.5 s
+        let mut config = test_config();
+        config.submission_latency = String::from("0.5");
+        config.retrieval_latency = String::from("0.25");
+        let output = run_workflow(config, "classical 1\nquantum 2 2\nclassical 1")?;
+        assert_eq!(vec![6.75], workflow_series(&output, "job_time"));
+        assert_eq!(
+            vec![2.0],
+            workflow_series(&output, "classical_service_time")
+        );
+        assert_eq!(
+            vec![0.0],
+            workflow_series(&output, "classical_waiting_time")
+        );
+        assert_eq!(vec![0.75], workflow_series(&output, "network_time"));
+        assert_eq!(vec![2.0], workflow_series(&output, "quantum_queueing_time"));
+        assert_eq!(vec![2.0], workflow_series(&output, "quantum_service_time"));
+
+        // between two quantum stages, the results are retrieved before the
+        // next quantum task is submitted, hence the second stage lasts from
+        // 3.5 s until 6.25 s
+        let mut config = test_config();
+        config.submission_latency = String::from("0.5");
+        config.retrieval_latency = String::from("0.25");
+        let output = run_workflow(config, "classical 1\nquantum 2\nquantum 2\nclassical 1")?;
+        assert_eq!(vec![7.5], workflow_series(&output, "job_time"));
+        assert_eq!(vec![1.5], workflow_series(&output, "network_time"));
+        assert_eq!(vec![4.0], workflow_series(&output, "quantum_service_time"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_multi_programming() -> anyhow::Result<()> {
+        // the jobs have a single quantum task, which is submitted at a time
+        // that does not depend on how the quantum computer is shared
+        let job_factory = || crate::job::JobFactory::for_testing(1.0, 0.1, 1.0, 1.5, 1);
+        let mut busy_times = vec![];
+        let mut output = None;
+        for multi_programming in ["", "1:0.1"] {
+            let mut config = stream_config();
+            config.quantum_computers = String::from("qpu:8:1");
+            config.multi_programming = multi_programming.to_string();
+            let mut simulation = Simulation::with_job_factory(config, job_factory())?;
+            output = Some(simulation.run());
+            let end = to_nanoseconds(simulation.config.duration);
+            busy_times.push(
+                (simulation.qpu_busy_times[0]
+                    + simulation.qpu_busy_since[0].map_or(0, |since| end - since))
+                    as f64,
+            );
+        }
+
+        // when two circuits share the quantum computer they both execute at
+        // half speed, hence the quantum computer is busy for the same time
+        // until the end of the simulation, but for the rounding to ns
+        assert!(busy_times[0] > 0.0);
+        assert!((busy_times[0] - busy_times[1]).abs() / busy_times[0] < 1e-6);
+
+        // at most two circuits fit together, and each loses 10% of its
+        // fidelity when it starts while the other is executing
+        let output = output.unwrap();
+        let level = metric(&output, "multi_programming_level").unwrap();
+        let fidelity = metric(&output, "fidelity").unwrap();
+        assert!(level > 1.0 && level <= 2.0);
+        assert!((fidelity - (1.0 - 0.1 * (level - 1.0))).abs() < 1e-9);
+
+        // the number of qubits that can be packed must be limited
+        let mut config = test_config();
+        config.multi_programming = String::from("1:0.1");
+        assert!(run(config, job_factory()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_downtimes() -> anyhow::Result<()> {
+        // the quantum task executes from 9 s to 13 s, if not interrupted by
+        // the downtime from 10 s to 12 s, followed by 1 s of post-processing
+        for (policy, expected_job_times, expected_aborted, expected_lost) in [
+            ("finish", vec![14.0], 0.0, 0.0),
+            ("abort", vec![], 1.0, 1.0),
+            ("requeue", vec![17.0], 0.0, 1.0),
+        ] {
+            let mut config = test_config();
+            config.downtimes = String::from("10:2");
+            config.downtime_policy = policy.to_string();
+            let job_factory = crate::job::JobFactory::for_testing(8.0, 1.0, 1.0, 4.0, 1);
+            let output = run(config, job_factory)?;
+            assert_eq!(
+                expected_job_times,
+                series(&output, "job_time"),
+                "{}",
+                policy
+            );
+            assert_eq!(Some(expected_aborted), metric(&output, "num_job_aborted"));
+            assert_eq!(Some(expected_lost), metric(&output, "lost_qpu_seconds"));
+        }
+
+        // with frequent downtimes, the simulator checks that no task ever
+        // starts on a quantum computer while it is offline, and only the
+        // tasks interrupted are lost
+        for policy in ["finish", "abort", "requeue"] {
+            let mut config = stream_config();
+            config.downtimes = String::from("5:1");
+            config.downtime_policy = policy.to_string();
+            let output = run(config, stream_job_factory())?;
+            let lost = metric(&output, "lost_qpu_seconds").unwrap();
+            let aborted = metric(&output, "num_job_aborted").unwrap();
+            assert_eq!(policy == "finish", lost == 0.0, "{}", policy);
+            assert_eq!(policy == "abort", aborted > 0.0, "{}", policy);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_failures() -> anyhow::Result<()> {
+        // the quantum computer either never fails or it fails so often that
+        // the quantum task never completes, after exhausting its retries
+        for (failures, expected_job_times, expected_failed, expected_retries) in [
+            ("1e12:1", vec![102.0], 0.0, 0.0),
+            ("0.1:0.1", vec![], 1.0, 2.0),
+        ] {
+            let mut config = test_config();
+            config.duration = 200.0;
+            config.failures = failures.to_string();
+            config.retry_budget = 2;
+            let job_factory = crate::job::JobFactory::for_testing(0.5, 0.5, 1.0, 100.0, 1);
+            let output = run(config, job_factory)?;
+            assert_eq!(
+                expected_job_times,
+                series(&output, "job_time"),
+                "{}",
+                failures
+            );
+            assert_eq!(Some(expected_failed), metric(&output, "num_job_failed"));
+            assert_eq!(Some(expected_retries), metric(&output, "num_retries"));
+        }
+
+        // with frequent failures, a job fails only after exhausting its
+        // retries, which never exceed the budget
+        for retry_budget in [0, 1, 2] {
+            let mut config = stream_config();
+            config.failures = String::from("5:1");
+            config.retry_budget = retry_budget;
+            let output = run(config, stream_job_factory())?;
+            let accepted = metric(&output, "num_job_accepted").unwrap();
+            let failed = metric(&output, "num_job_failed").unwrap();
+            let retries = metric(&output, "num_retries").unwrap();
+            assert!(failed > 0.0, "{}", retry_budget);
+            assert!(retries >= retry_budget as f64 * failed, "{}", retry_budget);
+            assert!(
+                retries <= retry_budget as f64 * accepted,
+                "{}",
+                retry_budget
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_cold_starts() -> anyhow::Result<()> {
+        // the function instance of the job is cold for the preparation phase
+        // and, if the quantum tasks are longer than the keep-alive time,
+        // after each of them, but the first cold start is not counted in the
+        // ratio because it happens before the end of the warm-up period
+        for (dur_qc, expected_job_time, expected_ratio) in
+            [(4.0, 14.0, 0.0), (10.0, 30.0, 2.0 / 3.0)]
+        {
+            let mut config = test_config();
+            config.cold_start = String::from("2");
+            config.keep_alive = 5.0;
+            let job_factory = crate::job::JobFactory::for_testing(1.0, 1.0, 1.0, dur_qc, 2);
+            let output = run(config, job_factory)?;
+            assert_eq!(vec![expected_job_time], series(&output, "job_time"));
+            assert_eq!(
+                Some(expected_ratio),
+                metric(&output, &cold_start_metric("VQE,4,1"))
+            );
+        }
+
+        // the longer the keep-alive time the fewer the cold starts, down to
+        // only one for each job, which has 5 classical tasks
+        let mut ratios = vec![];
+        for keep_alive in [0.0, 1.0, 1e9] {
+            let mut config = stream_config();
+            config.cold_start = String::from("0.5");
+            config.keep_alive = keep_alive;
+            let output = run(config, stream_job_factory())?;
+            ratios.push(metric(&output, &cold_start_metric("VQE,4,1")).unwrap());
+        }
+        assert!(ratios[0] > ratios[1] && ratios[1] > ratios[2]);
+        assert!(ratios[2] >= 0.2 && ratios[2] < 0.21);
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_autoscaler() -> anyhow::Result<()> {
+        // the preparation phase requires 4 workers, which are added at the
+        // first decision, after 3 s, and ready 1 s later, then they are
+        // removed at the first decision after it is complete, at 12 s
+        let mut config = test_config();
+        config.autoscaler = String::from("concurrency;0.25");
+        config.max_workers = 4;
+        config.scale_up_delay = 1.0;
+        config.autoscaler_interval = 3.0;
+        let job_factory = crate::job::JobFactory::for_testing(10.0, 1.0, 1.0, 1.0, 1);
+        let output = run(config, job_factory)?;
+        assert_eq!(vec![13.0], series(&output, "job_time"));
+        // the time average ends with the last change
+        assert_eq!(
+            Some((4.0 + 4.0 * 8.0) / 12.0),
+            metric(&output, "serverless_workers")
+        );
+
+        // with a varying load, the simulator checks that the number of
+        // workers remains within the bounds
+        let mut config = stream_config();
+        config.autoscaler = String::from("concurrency;0.5");
+        config.min_workers = 2;
+        config.max_workers = 3;
+        config.num_serverless_workers = 2;
+        let output = run(config, stream_job_factory())?;
+        let workers = metric(&output, "serverless_workers").unwrap();
+        assert!(workers > 2.0 && workers < 3.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_sessions() -> anyhow::Result<()> {
+        // the quantum computer is released only if the job does not use it
+        // for the idle timeout since its last quantum task
+        for (gap, expected_timeouts) in [(6, 0.0), (12, 2.0)] {
+            let mut config = test_config();
+            config.sessions = String::from("1:10");
+            let output = run_workflow(
+                config,
+                &format!("loop 3\nquantum 1\nclassical {}\nend", gap),
+            )?;
+            assert_eq!(
+                Some(expected_timeouts),
+                metric(&output, "num_session_timeouts")
+            );
+            assert_eq!(
+                vec![3.0 * (1 + gap) as f64],
+                workflow_series(&output, "job_time")
+            );
+        }
         Ok(())
     }
 }
@@ -9,7 +9,7 @@ pub enum TaskType {
     Quantum(u64),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Task {
     /// Job identifier.
     pub job_id: u64,
@@ -19,4 +19,20 @@ pub struct Task {
     pub start_time: u64,
     /// Last update time, in ns.
     pub last_update: u64,
+    /// Identifier of the resource executing the task, if any.
+    pub resource: Option<usize>,
+    /// Index of the task among those of the same stage of its job, which
+    /// are executed in parallel.
+    pub branch: usize,
+}
+
+impl Task {
+    /// Return the residual time of execution of a quantum task, in ns.
+    /// Panics if the task is not quantum.
+    pub fn quantum_duration(&self) -> u64 {
+        match self.task_type {
+            TaskType::Quantum(duration) => duration,
+            TaskType::Classical(_) => panic!("not a quantum task"),
+        }
+    }
 }
@@ -0,0 +1,458 @@
+// SPDX-FileCopyrightText: © 2024 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+use std::io::Write;
+
+/// Format of the trace of a simulation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    /// One JSON object per line for every event processed and every
+    /// task started or ended.
+    JsonLines,
+    /// Chrome trace-event format, with the tasks executed on every quantum
+    /// computer and serverless worker, which can be viewed with Perfetto.
+    Chrome,
+}
+
+impl std::str::FromStr for TraceFormat {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonl" => Ok(Self::JsonLines),
+            "chrome" => Ok(Self::Chrome),
+            _ => anyhow::bail!("cannot parse '{}' as trace format", s),
+        }
+    }
+}
+
+/// Record of the trace of a simulation, with times in ns.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceRecord {
+    /// An event has been processed, with its name and parameters.
+    Event {
+        time: u64,
+        name: &'static str,
+        args: Vec<u64>,
+    },
+    /// A task has started executing, on a given quantum computer or
+    /// serverless worker, if any.
+    TaskStart {
+        time: u64,
+        job_id: u64,
+        branch: usize,
+        quantum: bool,
+        resource: Option<usize>,
+    },
+    /// A task has stopped executing, because completed or interrupted.
+    TaskEnd {
+        time: u64,
+        job_id: u64,
+        branch: usize,
+        quantum: bool,
+        resource: Option<usize>,
+        completed: bool,
+    },
+}
+
+fn stage(quantum: bool) -> &'static str {
+    if quantum {
+        "quantum"
+    } else {
+        "classical"
+    }
+}
+
+fn resource_to_json(resource: Option<usize>) -> String {
+    match resource {
+        Some(resource) => resource.to_string(),
+        None => "null".to_string(),
+    }
+}
+
+impl TraceRecord {
+    /// Return the record as a JSON object.
+    /// Parameters:
+    /// - `seed`: the seed of the simulation
+    pub fn to_json(&self, seed: u64) -> String {
+        match self {
+            Self::Event { time, name, args } => format!(
+                "{{\"seed\":{},\"time\":{},\"type\":\"event\",\"event\":\"{}\",\"args\":[{}]}}",
+                seed,
+                time,
+                name,
+                args.iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ),
+            Self::TaskStart {
+                time,
+                job_id,
+                branch,
+                quantum,
+                resource,
+            } => format!(
+                "{{\"seed\":{},\"time\":{},\"type\":\"task_start\",\"job_id\":{},\"branch\":{},\"stage\":\"{}\",\"resource\":{}}}",
+                seed,
+                time,
+                job_id,
+                branch,
+                stage(*quantum),
+                resource_to_json(*resource)
+            ),
+            Self::TaskEnd {
+                time,
+                job_id,
+                branch,
+                quantum,
+                resource,
+                completed,
+            } => format!(
+                "{{\"seed\":{},\"time\":{},\"type\":\"task_end\",\"job_id\":{},\"branch\":{},\"stage\":\"{}\",\"resource\":{},\"completed\":{}}}",
+                seed,
+                time,
+                job_id,
+                branch,
+                stage(*quantum),
+                resource_to_json(*resource),
+                completed
+            ),
+        }
+    }
+}
+
+/// Destination of the traces of the simulations, in a given format.
+pub struct TraceWriter {
+    format: TraceFormat,
+    writer: Box<dyn std::io::Write + Send>,
+    /// Separator to be written before the next entry.
+    separator: &'static str,
+}
+
+/// Trace writer shared by the simulations running concurrently.
+pub type SharedTraceWriter = std::sync::Arc<std::sync::Mutex<TraceWriter>>;
+
+impl std::fmt::Debug for TraceWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TraceWriter")
+            .field("format", &self.format)
+            .finish()
+    }
+}
+
+impl TraceWriter {
+    /// Create a trace writer, which writes the beginning of the trace, if
+    /// any, to the given writer.
+    /// Parameters:
+    /// - `format`: the format of the trace
+    /// - `writer`: the destination of the trace, e.g., a file
+    pub fn new(
+        format: TraceFormat,
+        mut writer: Box<dyn std::io::Write + Send>,
+    ) -> std::io::Result<Self> {
+        let separator = match format {
+            TraceFormat::JsonLines => "",
+            TraceFormat::Chrome => {
+                write!(writer, "{{\"traceEvents\":[")?;
+                "\n"
+            }
+        };
+        Ok(Self {
+            format,
+            writer,
+            separator,
+        })
+    }
+
+    /// Write some entries of the trace.
+    fn write(&mut self, entries: &[String]) -> std::io::Result<()> {
+        for entry in entries {
+            match self.format {
+                TraceFormat::JsonLines => writeln!(self.writer, "{}", entry)?,
+                TraceFormat::Chrome => {
+                    write!(self.writer, "{}{}", self.separator, entry)?;
+                    self.separator = ",\n";
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the end of the trace, if any, and flush the writer.
+    /// No more entries can be written afterwards.
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        if self.format == TraceFormat::Chrome {
+            writeln!(self.writer, "\n]}}")?;
+        }
+        self.writer.flush()
+    }
+}
+
+/// Trace of a simulation, whose entries are written as the records are
+/// added, in chunks to limit the contention of the shared writer.
+///
+/// With the Chrome trace-event format, the tasks executed are complete
+/// events, with one process for the quantum computers and one for the
+/// serverless workers of every simulation, and one thread for every quantum
+/// computer or serverless worker. The classical tasks that are not assigned
+/// a specific worker are in one thread per job. A task that ends without
+/// having started in the trace, e.g., because it was not recorded, is an
+/// instant event at the time it ends.
+#[derive(Debug)]
+pub struct Trace {
+    seed: u64,
+    format: TraceFormat,
+    writer: SharedTraceWriter,
+    /// Entries not yet written.
+    entries: Vec<String>,
+    /// Start times of the tasks in execution, with the Chrome trace-event
+    /// format, indexed by job identifier, branch, and quantum stage.
+    started: std::collections::HashMap<(u64, usize, bool), u64>,
+}
+
+/// Maximum number of entries of a trace not yet written.
+const MAX_PENDING_ENTRIES: usize = 1024;
+
+impl Trace {
+    /// Create the trace of a simulation.
+    /// Parameters:
+    /// - `seed`: the seed of the simulation
+    /// - `writer`: the shared writer of the trace
+    pub fn new(seed: u64, writer: SharedTraceWriter) -> Self {
+        let format = writer.lock().unwrap().format;
+        let entries = match format {
+            TraceFormat::JsonLines => vec![],
+            TraceFormat::Chrome => [true, false]
+                .iter()
+                .map(|quantum| {
+                    format!(
+                        "{{\"name\":\"process_name\",\"ph\":\"M\",\"pid\":{},\"args\":{{\"name\":\"seed {} {}\"}}}}",
+                        chrome_pid(seed, *quantum),
+                        seed,
+                        if *quantum {
+                            "quantum computers"
+                        } else {
+                            "serverless workers"
+                        }
+                    )
+                })
+                .collect(),
+        };
+        Self {
+            seed,
+            format,
+            writer,
+            entries,
+            started: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Add a record to the trace.
+    pub fn add(&mut self, record: TraceRecord) -> std::io::Result<()> {
+        match self.format {
+            TraceFormat::JsonLines => self.entries.push(record.to_json(self.seed)),
+            TraceFormat::Chrome => {
+                if let Some(entry) = self.chrome_event(&record) {
+                    self.entries.push(entry);
+                }
+            }
+        }
+        if self.entries.len() >= MAX_PENDING_ENTRIES {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Write the entries not yet written.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        if !self.entries.is_empty() {
+            self.writer.lock().unwrap().write(&self.entries)?;
+            self.entries.clear();
+        }
+        Ok(())
+    }
+
+    /// Return the event of the Chrome trace-event format of a record, if any.
+    fn chrome_event(&mut self, record: &TraceRecord) -> Option<String> {
+        match record {
+            TraceRecord::TaskStart {
+                time,
+                job_id,
+                branch,
+                quantum,
+                ..
+            } => {
+                self.started.insert((*job_id, *branch, *quantum), *time);
+                None
+            }
+            TraceRecord::TaskEnd {
+                time,
+                job_id,
+                branch,
+                quantum,
+                resource,
+                completed,
+            } => {
+                let pid = chrome_pid(self.seed, *quantum);
+                let tid = match resource {
+                    Some(resource) => *resource as u64,
+                    None => 1_000_000 + *job_id,
+                };
+                Some(match self.started.remove(&(*job_id, *branch, *quantum)) {
+                    Some(start) => format!(
+                        "{{\"name\":\"job {}\",\"cat\":\"{}\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":{},\"tid\":{},\"args\":{{\"completed\":{}}}}}",
+                        job_id,
+                        stage(*quantum),
+                        start as f64 / 1e3,
+                        (*time - start) as f64 / 1e3,
+                        pid,
+                        tid,
+                        completed
+                    ),
+                    None => format!(
+                        "{{\"name\":\"job {}\",\"cat\":\"{}\",\"ph\":\"i\",\"s\":\"t\",\"ts\":{},\"pid\":{},\"tid\":{},\"args\":{{\"completed\":{}}}}}",
+                        job_id,
+                        stage(*quantum),
+                        *time as f64 / 1e3,
+                        pid,
+                        tid,
+                        completed
+                    ),
+                })
+            }
+            TraceRecord::Event { .. } => None,
+        }
+    }
+}
+
+/// Return the process of the Chrome trace-event format of the quantum
+/// computers or serverless workers of a simulation.
+fn chrome_pid(seed: u64, quantum: bool) -> u64 {
+    2 * seed + if quantum { 0 } else { 1 }
+}
+
+/// Buffer in memory shared with a trace writer, for testing.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct TestBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+#[cfg(test)]
+impl std::io::Write for TestBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl TestBuffer {
+    /// Return a trace writer to this buffer.
+    pub(crate) fn trace_writer(&self, format: TraceFormat) -> SharedTraceWriter {
+        std::sync::Arc::new(std::sync::Mutex::new(
+            TraceWriter::new(format, Box::new(self.clone())).unwrap(),
+        ))
+    }
+
+    /// Return the content of the buffer, one entry per line.
+    pub(crate) fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(|x| x.to_string())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace() -> anyhow::Result<()> {
+        use std::str::FromStr;
+
+        assert_eq!(TraceFormat::JsonLines, TraceFormat::from_str("jsonl")?);
+        assert_eq!(TraceFormat::Chrome, TraceFormat::from_str("chrome")?);
+        assert!(TraceFormat::from_str("json").is_err());
+
+        let records = vec![
+            TraceRecord::Event {
+                time: 0,
+                name: "JobStart",
+                args: vec![],
+            },
+            TraceRecord::TaskStart {
+                time: 1000,
+                job_id: 3,
+                branch: 1,
+                quantum: true,
+                resource: Some(2),
+            },
+            TraceRecord::TaskStart {
+                time: 1500,
+                job_id: 4,
+                branch: 0,
+                quantum: false,
+                resource: None,
+            },
+            TraceRecord::TaskEnd {
+                time: 4000,
+                job_id: 3,
+                branch: 1,
+                quantum: true,
+                resource: Some(2),
+                completed: false,
+            },
+            TraceRecord::TaskEnd {
+                time: 5000,
+                job_id: 5,
+                branch: 0,
+                quantum: false,
+                resource: Some(1),
+                completed: true,
+            },
+        ];
+        let write_trace = |format| -> anyhow::Result<Vec<String>> {
+            let buffer = TestBuffer::default();
+            let writer = buffer.trace_writer(format);
+            let mut trace = Trace::new(42, writer.clone());
+            for record in &records {
+                trace.add(record.clone())?;
+            }
+            trace.flush()?;
+            writer.lock().unwrap().finish()?;
+            Ok(buffer.lines())
+        };
+
+        let lines = write_trace(TraceFormat::JsonLines)?;
+        assert_eq!(5, lines.len());
+        assert_eq!(
+            "{\"seed\":42,\"time\":0,\"type\":\"event\",\"event\":\"JobStart\",\"args\":[]}",
+            lines[0]
+        );
+        assert_eq!(
+            "{\"seed\":42,\"time\":1500,\"type\":\"task_start\",\"job_id\":4,\"branch\":0,\"stage\":\"classical\",\"resource\":null}",
+            lines[2]
+        );
+
+        // two process names, the only task started and ended, and the task
+        // ended without a start, between the beginning and end of the trace
+        let lines = write_trace(TraceFormat::Chrome)?;
+        assert_eq!(6, lines.len());
+        assert_eq!("{\"traceEvents\":[", lines[0]);
+        assert_eq!(
+            "{\"name\":\"job 3\",\"cat\":\"quantum\",\"ph\":\"X\",\"ts\":1,\"dur\":3,\"pid\":84,\"tid\":2,\"args\":{\"completed\":false}},",
+            lines[3]
+        );
+        assert_eq!(
+            "{\"name\":\"job 5\",\"cat\":\"classical\",\"ph\":\"i\",\"s\":\"t\",\"ts\":5,\"pid\":85,\"tid\":1,\"args\":{\"completed\":true}}",
+            lines[4]
+        );
+        assert_eq!("]}", lines[5]);
+
+        Ok(())
+    }
+}
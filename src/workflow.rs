@@ -0,0 +1,275 @@
+// SPDX-FileCopyrightText: © 2024 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+use rand_distr::Distribution;
+
+/// Number of times the body of a loop is executed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TripCount {
+    /// Always the same number of times.
+    Fixed(u64),
+    /// Uniformly distributed between a minimum and a maximum, inclusive.
+    Uniform(u64, u64),
+}
+
+impl TripCount {
+    /// Draw a random number of times.
+    fn sample(&self, rng: &mut rand::rngs::StdRng) -> u64 {
+        match self {
+            Self::Fixed(value) => *value,
+            Self::Uniform(min, max) => rand_distr::Uniform::new_inclusive(min, max).sample(rng),
+        }
+    }
+}
+
+impl std::str::FromStr for TripCount {
+    type Err = anyhow::Error;
+    /// Parse a trip count, specified either as `N` or as `MIN:MAX`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(':')
+            .map(|x| x.parse::<u64>())
+            .collect::<Result<Vec<u64>, _>>()
+            .map_err(|_| anyhow::anyhow!("invalid trip count: {}", s))?;
+        match values[..] {
+            [value] => Ok(Self::Fixed(value)),
+            [min, max] if min <= max => Ok(Self::Uniform(min, max)),
+            _ => anyhow::bail!("invalid trip count: {}", s),
+        }
+    }
+}
+
+/// Statement of the description of a workflow.
+#[derive(Debug, Clone, PartialEq)]
+enum Statement {
+    /// Classical task of a given phase, with the distribution of its
+    /// number of operations.
+    Classical(crate::job::Phase, crate::latency::LatencyDistribution),
+    /// Quantum tasks executed in parallel, with the distribution of their
+    /// durations and their number.
+    Quantum(crate::latency::LatencyDistribution, usize),
+    /// Statements executed a random number of times.
+    Loop(TripCount, Vec<Statement>),
+}
+
+/// Description of the workflow of a job, with one statement per line
+/// (anything after `#` is a comment):
+/// - `classical DIST`: a classical task, whose number of operations is
+///   expressed, like in the traces, as the time in s to execute it with
+///   a capacity of 10^9 operations/s
+/// - `quantum DIST [BRANCHES]`: a stage of quantum tasks, 1 by default,
+///   executed in parallel, each with its own duration in s, which must all
+///   complete before the next stage starts
+/// - `loop COUNT` ... `end`: the statements in between are repeated `N` or
+///   `MIN:MAX` times, drawn uniformly, and loops can be nested
+///
+/// where `DIST` is a distribution as in the network latencies, e.g., `0.5`,
+/// `exp:0.5`, or `uniform:0.1:0.9`.
+/// The classical tasks before the first quantum stage or loop are accounted
+/// as preparation, those after the last one as post-processing, and all the
+/// others as classical iterations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workflow {
+    statements: Vec<Statement>,
+}
+
+impl Workflow {
+    /// Read the description of a workflow from a file.
+    /// Parameters:
+    /// - `filename`: the name of the file
+    pub fn from_file(filename: &str) -> anyhow::Result<Self> {
+        let description = std::fs::read_to_string(filename)
+            .map_err(|err| anyhow::anyhow!("cannot read workflow '{}': {}", filename, err))?;
+        description
+            .parse()
+            .map_err(|err| anyhow::anyhow!("invalid workflow '{}': {}", filename, err))
+    }
+
+    /// Draw the stages of a job executing the workflow, with loops unrolled.
+    /// Parameters:
+    /// - `rng`: the pseudo-random number generator
+    pub fn stages(&self, rng: &mut rand::rngs::StdRng) -> Vec<crate::job::Stage> {
+        let mut stages = vec![];
+        Self::unroll(&self.statements, rng, &mut stages);
+        stages
+    }
+
+    fn unroll(
+        statements: &[Statement],
+        rng: &mut rand::rngs::StdRng,
+        stages: &mut Vec<crate::job::Stage>,
+    ) {
+        for statement in statements {
+            match statement {
+                Statement::Classical(phase, num_operations) => {
+                    stages.push(crate::job::Stage::Classical(
+                        *phase,
+                        num_operations.sample(rng),
+                    ));
+                }
+                Statement::Quantum(duration, num_branches) => {
+                    stages.push(crate::job::Stage::Quantum(
+                        (0..*num_branches).map(|_| duration.sample(rng)).collect(),
+                    ));
+                }
+                Statement::Loop(trip_count, body) => {
+                    for _ in 0..trip_count.sample(rng) {
+                        Self::unroll(body, rng, stages);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Parse a line of the description into the innermost open block.
+    fn parse_line(
+        tokens: &[&str],
+        blocks: &mut Vec<(Option<TripCount>, Vec<Statement>)>,
+    ) -> anyhow::Result<()> {
+        match (tokens[0], tokens.len()) {
+            ("classical", 2) => {
+                let num_operations = tokens[1].parse()?;
+                blocks.last_mut().unwrap().1.push(Statement::Classical(
+                    crate::job::Phase::ClassicalIteration,
+                    num_operations,
+                ));
+            }
+            ("quantum", 2) | ("quantum", 3) => {
+                let duration = tokens[1].parse()?;
+                let num_branches = match tokens.get(2) {
+                    Some(token) => token
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|x| *x > 0)
+                        .ok_or(anyhow::anyhow!("invalid number of branches: {}", token))?,
+                    None => 1,
+                };
+                blocks
+                    .last_mut()
+                    .unwrap()
+                    .1
+                    .push(Statement::Quantum(duration, num_branches));
+            }
+            ("loop", 2) => {
+                blocks.push((Some(tokens[1].parse()?), vec![]));
+            }
+            ("end", 1) => {
+                anyhow::ensure!(blocks.len() > 1, "end without loop");
+                let (trip_count, body) = blocks.pop().unwrap();
+                blocks
+                    .last_mut()
+                    .unwrap()
+                    .1
+                    .push(Statement::Loop(trip_count.unwrap(), body));
+            }
+            _ => anyhow::bail!("invalid statement: {}", tokens.join(" ")),
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Workflow {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut blocks = vec![(None, vec![])];
+        for (line_number, line) in s.lines().enumerate() {
+            let tokens = line
+                .split('#')
+                .next()
+                .unwrap()
+                .split_whitespace()
+                .collect::<Vec<&str>>();
+            if tokens.is_empty() {
+                continue;
+            }
+            Self::parse_line(&tokens, &mut blocks)
+                .map_err(|err| anyhow::anyhow!("line {}: {}", line_number + 1, err))?;
+        }
+        anyhow::ensure!(blocks.len() == 1, "loop without end");
+        let mut statements = blocks.pop().unwrap().1;
+        anyhow::ensure!(!statements.is_empty(), "empty workflow");
+
+        // only the top-level classical tasks can be preparation or
+        // post-processing, depending on their position
+        let is_iteration = |statement: &Statement| !matches!(statement, Statement::Classical(..));
+        let first = statements.iter().position(is_iteration);
+        let last = statements.iter().rposition(is_iteration);
+        for (index, statement) in statements.iter_mut().enumerate() {
+            if let Statement::Classical(phase, _) = statement {
+                *phase = if first.is_none_or(|first| index < first) {
+                    crate::job::Phase::Preparation
+                } else if last.is_some_and(|last| index > last) {
+                    crate::job::Phase::Postprocessing
+                } else {
+                    crate::job::Phase::ClassicalIteration
+                };
+            }
+        }
+
+        Ok(Self { statements })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_workflow() -> anyhow::Result<()> {
+        use crate::job::{Phase, Stage};
+        use std::str::FromStr;
+
+        let workflow = Workflow::from_str(
+            r#"
+# error mitigation
+classical 1
+loop 2
+  classical 0.5 # optimizer
+  quantum 0.1 3
+  loop 1:1
+    quantum 0.2
+  end
+end
+classical 2
+"#,
+        )?;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let second = 1_000_000_000;
+        let iteration = [
+            Stage::Classical(Phase::ClassicalIteration, second / 2),
+            Stage::Quantum(vec![second / 10; 3]),
+            Stage::Quantum(vec![second / 5]),
+        ];
+        let mut expected = vec![Stage::Classical(Phase::Preparation, second)];
+        expected.extend_from_slice(&iteration);
+        expected.extend_from_slice(&iteration);
+        expected.push(Stage::Classical(Phase::Postprocessing, 2 * second));
+        assert_eq!(expected, workflow.stages(&mut rng));
+
+        // the trip counts are drawn for every job and every loop
+        let workflow = Workflow::from_str("loop 0:3\nquantum 1\nend")?;
+        let mut num_stages = std::collections::BTreeSet::new();
+        for _ in 0..100 {
+            num_stages.insert(workflow.stages(&mut rng).len());
+        }
+        assert_eq!(vec![0, 1, 2, 3], num_stages.into_iter().collect::<Vec<_>>());
+
+        for invalid in [
+            "",
+            "# nothing",
+            "classical",
+            "classical x",
+            "quantum 1 0",
+            "quantum 1 2 3",
+            "loop 2\nquantum 1",
+            "quantum 1\nend",
+            "loop 3:2\nquantum 1\nend",
+            "measure 1",
+        ] {
+            assert!(Workflow::from_str(invalid).is_err(), "{}", invalid);
+        }
+
+        Ok(())
+    }
+}